@@ -1,7 +1,10 @@
 use criterion::*;
 use std::sync::Mutex;
 use std::thread;
+use wal::builder::Builder;
 use wal::doubly;
+use wal::queue::Queue;
+use wal::reclaim::{EpochReclaimer, HazardEraReclaimer, LeakReclaimer, Reclaimer, SeizeReclaimer};
 use wal::LinkedList;
 
 fn pop_front(c: &mut Criterion) {
@@ -25,10 +28,7 @@ fn pop_front(c: &mut Criterion) {
         });
 
         group.bench_with_input(BenchmarkId::new("wal", t), &t, |b, &t| {
-            let list = LinkedList::new();
-            for i in 0..10000 {
-                list.push_back(i);
-            }
+            let list = LinkedList::from_iter(0..10000);
             b.iter(|| {
                 thread::scope(|s| {
                     for _ in 1..=t {
@@ -43,10 +43,7 @@ fn pop_front(c: &mut Criterion) {
         });
 
         group.bench_with_input(BenchmarkId::new("wal::doubly", t), &t, |b, &t| {
-            let list = doubly::LinkedList::new();
-            for i in 0..10000 {
-                list.push_back(i);
-            }
+            let list = doubly::LinkedList::from_iter(0..10000);
             b.iter(|| {
                 thread::scope(|s| {
                     for _ in 1..=t {
@@ -83,10 +80,7 @@ fn pop_back(c: &mut Criterion) {
         });
 
         group.bench_with_input(BenchmarkId::new("wal::doubly", t), &t, |b, &t| {
-            let list = doubly::LinkedList::new();
-            for i in 0..10000 {
-                list.push_back(i);
-            }
+            let list = doubly::LinkedList::from_iter(0..10000);
             b.iter(|| {
                 thread::scope(|s| {
                     for _ in 1..=t {
@@ -114,7 +108,7 @@ fn push_front(c: &mut Criterion) {
                     for _ in 1..=t {
                         s.spawn(|| {
                             for i in 0..100 {
-                                let _ = list.lock().unwrap().push_front(i);
+                                list.lock().unwrap().push_front(i);
                             }
                         });
                     }
@@ -138,16 +132,13 @@ fn push_front(c: &mut Criterion) {
         });
 
         group.bench_with_input(BenchmarkId::new("wal::doubly", t), &t, |b, &t| {
-            let list = doubly::LinkedList::new();
-            for i in 0..10000 {
-                list.push_back(i);
-            }
+            let list = doubly::LinkedList::from_iter(0..10000);
             b.iter(|| {
                 thread::scope(|s| {
                     for _ in 1..=t {
                         s.spawn(|| {
                             for i in 0..100 {
-                                let _ = list.push_front(i);
+                                list.push_front(i);
                             }
                         });
                     }
@@ -169,7 +160,7 @@ fn push_back(c: &mut Criterion) {
                     for _ in 1..=t {
                         s.spawn(|| {
                             for i in 0..100 {
-                                let _ = list.lock().unwrap().push_back(i);
+                                list.lock().unwrap().push_back(i);
                             }
                         });
                     }
@@ -193,7 +184,7 @@ fn push_back(c: &mut Criterion) {
         });
 
         group.bench_with_input(BenchmarkId::new("wal::doubly", t), &t, |b, &t| {
-            let list = LinkedList::new();
+            let list = doubly::LinkedList::new();
             b.iter(|| {
                 thread::scope(|s| {
                     for _ in 1..=t {
@@ -209,5 +200,192 @@ fn push_back(c: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, pop_front, pop_back, push_front, push_back);
+fn batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("batch");
+    for t in 1..=4 {
+        // every iteration pushes or pops 1000 elements per thread
+        group.throughput(criterion::Throughput::Elements(1000 * t as u64));
+
+        // every iteration gets a fresh queue and hands it back, so that
+        // building, filling and dropping it stay outside the timing
+        group.bench_with_input(BenchmarkId::new("queue::push_back", t), &t, |b, &t| {
+            b.iter_batched(
+                Queue::new,
+                |queue| {
+                    thread::scope(|s| {
+                        for _ in 1..=t {
+                            s.spawn(|| {
+                                for i in 0..1000 {
+                                    queue.push_back(i);
+                                }
+                            });
+                        }
+                    });
+                    queue
+                },
+                BatchSize::LargeInput,
+            );
+        });
+
+        group.bench_with_input(
+            BenchmarkId::new("queue::push_back_batch", t),
+            &t,
+            |b, &t| {
+                b.iter_batched(
+                    Queue::new,
+                    |queue| {
+                        thread::scope(|s| {
+                            for _ in 1..=t {
+                                s.spawn(|| queue.push_back_batch(0..1000));
+                            }
+                        });
+                        queue
+                    },
+                    BatchSize::LargeInput,
+                );
+            },
+        );
+
+        group.bench_with_input(BenchmarkId::new("queue::pop_front", t), &t, |b, &t| {
+            b.iter_batched(
+                || Queue::from_iter(0..1000 * t),
+                |queue| {
+                    thread::scope(|s| {
+                        for _ in 1..=t {
+                            s.spawn(|| {
+                                for _ in 0..1000 {
+                                    queue.pop_front().unwrap();
+                                }
+                            });
+                        }
+                    });
+                    queue
+                },
+                BatchSize::LargeInput,
+            );
+        });
+
+        group.bench_with_input(
+            BenchmarkId::new("queue::pop_front_batch", t),
+            &t,
+            |b, &t| {
+                b.iter_batched(
+                    || Queue::from_iter(0..1000 * t),
+                    |queue| {
+                        thread::scope(|s| {
+                            for _ in 1..=t {
+                                s.spawn(|| {
+                                    let mut out = Vec::with_capacity(1000);
+                                    while out.len() < 1000 {
+                                        queue.pop_front_batch((1000 - out.len()).min(64), &mut out);
+                                    }
+                                });
+                            }
+                        });
+                        queue
+                    },
+                    BatchSize::LargeInput,
+                );
+            },
+        );
+    }
+}
+
+fn reclaim(c: &mut Criterion) {
+    fn push_pop<R: Reclaimer + Default>(
+        group: &mut BenchmarkGroup<'_, measurement::WallTime>,
+        name: &str,
+        t: usize,
+    ) {
+        group.bench_with_input(BenchmarkId::new(name, t), &t, |b, &t| {
+            let queue = Queue::with_reclaimer(R::default());
+            b.iter(|| {
+                thread::scope(|s| {
+                    for _ in 1..=t {
+                        s.spawn(|| {
+                            for i in 0..1000 {
+                                queue.push_back(i);
+                                let _ = queue.pop_front();
+                            }
+                        });
+                    }
+                });
+            });
+        });
+    }
+
+    let mut group = c.benchmark_group("reclaim");
+    for t in 1..=4 {
+        group.throughput(criterion::Throughput::Elements(t as u64));
+        push_pop::<SeizeReclaimer>(&mut group, "seize", t);
+        push_pop::<EpochReclaimer>(&mut group, "epoch", t);
+        push_pop::<HazardEraReclaimer>(&mut group, "hazard_era", t);
+        push_pop::<LeakReclaimer>(&mut group, "leak", t);
+    }
+}
+
+fn recycle(c: &mut Criterion) {
+    let mut group = c.benchmark_group("recycle");
+    for t in [1, 2, 4, 8, 16] {
+        group.throughput(criterion::Throughput::Elements(t as u64));
+        for recycle in [false, true] {
+            let name = if recycle { "recycle" } else { "box" };
+            let builder = Builder::new().recycle(recycle);
+
+            group.bench_with_input(BenchmarkId::new(format!("queue/{name}"), t), &t, |b, &t| {
+                let queue: Queue<i32> = builder.clone().build();
+                b.iter(|| {
+                    thread::scope(|s| {
+                        for _ in 1..=t {
+                            s.spawn(|| {
+                                for i in 0..1000 {
+                                    queue.push_back(i);
+                                    let _ = queue.pop_front();
+                                }
+                            });
+                        }
+                    });
+                });
+            });
+
+            group.bench_with_input(BenchmarkId::new(format!("wal/{name}"), t), &t, |b, &t| {
+                let list: LinkedList<i32> = builder.clone().build();
+                b.iter(|| {
+                    thread::scope(|s| {
+                        for _ in 1..=t {
+                            s.spawn(|| {
+                                for i in 0..1000 {
+                                    list.push_front(i);
+                                    let _ = list.pop_front();
+                                }
+                            });
+                        }
+                    });
+                });
+            });
+
+            group.bench_with_input(
+                BenchmarkId::new(format!("wal::doubly/{name}"), t),
+                &t,
+                |b, &t| {
+                    let list: doubly::LinkedList<i32> = builder.clone().build();
+                    b.iter(|| {
+                        thread::scope(|s| {
+                            for _ in 1..=t {
+                                s.spawn(|| {
+                                    for i in 0..1000 {
+                                        list.push_back(i);
+                                        let _ = list.pop_back();
+                                    }
+                                });
+                            }
+                        });
+                    });
+                },
+            );
+        }
+    }
+}
+
+criterion_group!(benches, pop_front, pop_back, push_front, push_back, batch, reclaim, recycle);
 criterion_main!(benches);
@@ -0,0 +1,269 @@
+//! Configuring a container's reclamation before it is created.
+//!
+//! ```
+//! use wal::builder::Builder;
+//! use wal::queue::Queue;
+//!
+//! let queue: Queue<u64> = Builder::new()
+//!     .batch_size(512)
+//!     .threads(256)
+//!     .preallocate(64)
+//!     .capacity(1024)
+//!     .build();
+//! queue.push_back(1);
+//! assert_eq!(queue.pop_front(), Some(1));
+//! ```
+
+use std::fmt;
+use std::num::NonZeroU64;
+use std::sync::Arc;
+
+#[cfg(feature = "instrument")]
+use crate::instrument::Hook;
+use crate::reclaim::{self, Collector, SeizeReclaimer};
+
+// seize's own default, which is not exported
+const DEFAULT_BATCH_SIZE: usize = 120;
+
+/// Builds a [`Queue`](crate::queue::Queue), a
+/// [`LinkedList`](crate::LinkedList) or a
+/// [`doubly::LinkedList`](crate::doubly::LinkedList) with its own tuned
+/// [`Collector`], or one shared with other containers.
+#[derive(Clone, Default)]
+pub struct Builder {
+    collector: Option<Arc<Collector>>,
+    batch_size: Option<usize>,
+    epoch_frequency: Option<Option<NonZeroU64>>,
+    threads: Option<usize>,
+    recycle: bool,
+    pub(crate) preallocate: usize,
+    pub(crate) capacity: Option<usize>,
+    #[cfg(feature = "instrument")]
+    pub(crate) hook: Option<Arc<dyn Hook>>,
+}
+
+/// A container that can be created from a [`Builder`].
+pub trait Build: Sized {
+    fn from_builder(builder: Builder) -> Self;
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how many nodes a thread retires before trying to free them.
+    ///
+    /// Smaller batches bound the memory held by retired nodes more tightly,
+    /// at the cost of reclaiming more often. See
+    /// [`Collector::batch_size`].
+    pub fn batch_size(mut self, n: usize) -> Self {
+        self.batch_size = Some(n);
+        self
+    }
+
+    /// Sets how many nodes are allocated between epoch advances, or `None`
+    /// to stop tracking stalled threads. See [`Collector::epoch_frequency`].
+    pub fn epoch_frequency(mut self, n: Option<NonZeroU64>) -> Self {
+        self.epoch_frequency = Some(n);
+        self
+    }
+
+    /// Hints at how many threads will use the container.
+    ///
+    /// Unless [`Builder::batch_size`] is set, batches are made large enough
+    /// for that many threads to retire into them, which seize needs to
+    /// reclaim efficiently.
+    pub fn threads(mut self, n: usize) -> Self {
+        self.threads = Some(n);
+        self
+    }
+
+    /// Recycles reclaimed nodes through per-thread free lists, see
+    /// [`SeizeReclaimer::recycle`].
+    pub fn recycle(mut self, recycle: bool) -> Self {
+        self.recycle = recycle;
+        self
+    }
+
+    /// Allocates `n` nodes up front into the building thread's free list, so
+    /// that its first `n` pushes do not go to the global allocator.
+    ///
+    /// Implies [`Builder::recycle`]. A thread's free list holds at most 256
+    /// nodes of a given size, larger hints are capped to that.
+    pub fn preallocate(mut self, n: usize) -> Self {
+        self.preallocate = n;
+        self
+    }
+
+    /// Retires nodes into `collector`, which may be shared with other
+    /// containers.
+    ///
+    /// The collector keeps its own settings, building with any of
+    /// [`Builder::batch_size`], [`Builder::epoch_frequency`] or
+    /// [`Builder::threads`] set as well panics.
+    pub fn collector(mut self, collector: Arc<Collector>) -> Self {
+        self.collector = Some(collector);
+        self
+    }
+
+    /// Bounds a queue to `capacity` elements.
+    ///
+    /// The lists are unbounded, building one with a capacity panics.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Reports the container's [`Event`](crate::instrument::Event)s to
+    /// `hook`, as well as to the process-wide hook if one is installed.
+    #[cfg(feature = "instrument")]
+    pub fn hook(mut self, hook: impl Hook + 'static) -> Self {
+        self.hook = Some(Arc::new(hook));
+        self
+    }
+
+    pub fn build<C: Build>(self) -> C {
+        C::from_builder(self)
+    }
+
+    pub(crate) fn reclaimer(&self) -> SeizeReclaimer {
+        let recycle = self.recycle || self.preallocate > 0;
+        if let Some(collector) = &self.collector {
+            assert!(
+                self.batch_size.is_none()
+                    && self.epoch_frequency.is_none()
+                    && self.threads.is_none(),
+                "a shared collector cannot be tuned by the builder"
+            );
+            return SeizeReclaimer::shared(collector.clone()).recycle(recycle);
+        }
+
+        let mut collector = reclaim::collector();
+        if let Some(n) = self.batch_size.or(self.threads.map(batch_for)) {
+            collector = collector.batch_size(n);
+        }
+        if let Some(n) = self.epoch_frequency {
+            collector = collector.epoch_frequency(n);
+        }
+
+        SeizeReclaimer::shared(Arc::new(collector)).recycle(recycle)
+    }
+
+    pub(crate) fn unbounded(&self) {
+        assert!(self.capacity.is_none(), "only queues can be bounded");
+    }
+}
+
+fn batch_for(threads: usize) -> usize {
+    DEFAULT_BATCH_SIZE.max(threads.saturating_mul(2))
+}
+
+impl fmt::Debug for Builder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Builder")
+            .field("collector", &self.collector)
+            .field("batch_size", &self.batch_size)
+            .field("epoch_frequency", &self.epoch_frequency)
+            .field("threads", &self.threads)
+            .field("recycle", &self.recycle)
+            .field("preallocate", &self.preallocate)
+            .field("capacity", &self.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Barrier, thread};
+
+    use super::*;
+    use crate::{doubly, queue::Queue, LinkedList};
+
+    #[test]
+    fn build_all() {
+        let builder = Builder::new()
+            .batch_size(8)
+            .epoch_frequency(NonZeroU64::new(4));
+
+        let queue: Queue<u32> = builder.clone().capacity(2).build();
+        let list: LinkedList<u32> = builder.clone().build();
+        let doubly: doubly::LinkedList<u32> = builder.build();
+
+        for i in 0..64 {
+            queue.push_back(i);
+            list.push_back(queue.pop_front().unwrap());
+            doubly.push_front(list.pop_front().unwrap());
+            assert_eq!(doubly.pop_back(), Some(i));
+        }
+
+        assert_eq!(queue.capacity(), Some(2));
+        assert!(queue.try_push_back(1).is_ok());
+        assert!(queue.try_push_back(2).is_ok());
+        assert!(queue.try_push_back(3).is_err());
+    }
+
+    #[test]
+    fn threads_hint() {
+        const THREADS: usize = if cfg!(miri) { 16 } else { 200 };
+
+        let queue: Queue<usize> = Builder::new().threads(THREADS).build();
+        let b = Barrier::new(THREADS);
+        thread::scope(|s| {
+            for i in 0..THREADS {
+                let (queue, b) = (&queue, &b);
+                s.spawn(move || {
+                    b.wait();
+                    queue.push_back(i);
+                    queue.pop_front().unwrap();
+                });
+            }
+        });
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn preallocate() {
+        let builder = Builder::new().preallocate(8);
+        assert!(format!("{:?}", builder.reclaimer()).contains("recycle: true"));
+
+        let queue: Queue<u32> = builder.clone().build();
+        let list: LinkedList<u32> = builder.clone().build();
+        let doubly: doubly::LinkedList<u32> = builder.build();
+        for i in 0..16 {
+            queue.push_back(i);
+            list.push_back(i);
+            doubly.push_front(i);
+        }
+        assert!(queue.into_iter().eq(0..16));
+        assert!(list.into_iter().eq(0..16));
+        assert!(doubly.into_iter().rev().eq(0..16));
+    }
+
+    #[test]
+    fn shared() {
+        let collector = Arc::new(Collector::new());
+        let a: Queue<u32> = Builder::new().collector(collector.clone()).build();
+        let b: LinkedList<u32> = Builder::new().collector(collector.clone()).build();
+
+        let guard = collector.enter();
+        a.push_back_with(1, &guard);
+        b.push_front_with(a.pop_front_with(&guard).unwrap(), &guard);
+        assert_eq!(b.front(&guard), Some(1));
+    }
+
+    #[test]
+    #[should_panic = "a shared collector cannot be tuned by the builder"]
+    fn tuned_shared() {
+        let _: Queue<u32> = Builder::new()
+            .collector(Arc::new(Collector::new()))
+            .batch_size(8)
+            .build();
+    }
+
+    #[test]
+    #[should_panic = "only queues can be bounded"]
+    fn bounded_list() {
+        let _: doubly::LinkedList<u32> = Builder::new().capacity(8).build();
+    }
+}
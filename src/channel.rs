@@ -0,0 +1,380 @@
+//! Multi-producer multi-consumer channels on top of [`Queue`].
+//!
+//! The channel is closed for receivers once every [`Sender`] is dropped:
+//! they drain what is left and then get [`Disconnected`]. Likewise sending
+//! fails once every [`Receiver`] is gone.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::{error, fmt};
+
+use crate::queue::{Full, Queue};
+
+struct Chan<T> {
+    queue: Queue<T>,
+    senders: AtomicUsize,
+    receivers: AtomicUsize,
+}
+
+// Unlike the queue itself, the channel never hands out `&T`, so it only
+// needs `T: Send` to be shared between threads.
+unsafe impl<T: Send> Send for Chan<T> {}
+unsafe impl<T: Send> Sync for Chan<T> {}
+
+impl<T> Chan<T> {
+    fn new(queue: Queue<T>) -> Arc<Self> {
+        Arc::new(Self {
+            queue,
+            senders: AtomicUsize::new(1),
+            receivers: AtomicUsize::new(1),
+        })
+    }
+
+    fn senders_gone(&self) -> bool {
+        self.senders.load(Ordering::Acquire) == 0
+    }
+
+    fn receivers_gone(&self) -> bool {
+        self.receivers.load(Ordering::Acquire) == 0
+    }
+}
+
+/// Creates a channel that can hold any number of messages.
+pub fn unbounded<T>() -> (Sender<T>, Receiver<T>) {
+    let chan = Chan::new(Queue::new());
+    (Sender { chan: chan.clone() }, Receiver { chan })
+}
+
+/// Creates a channel that holds at most `capacity` messages, senders wait
+/// for room once it is full.
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero. Messages are always buffered, there is no
+/// handoff of a send to a waiting receiver.
+pub fn bounded<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(
+        capacity > 0,
+        "a bounded channel needs a capacity of at least 1"
+    );
+    let chan = Chan::new(Queue::with_capacity(capacity));
+    (Sender { chan: chan.clone() }, Receiver { chan })
+}
+
+pub struct Sender<T> {
+    chan: Arc<Chan<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Sends a message, waiting for room if the channel is bounded and full.
+    ///
+    /// Fails, handing the message back, once every receiver is dropped.
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        let queue = &self.chan.queue;
+        let connected = queue.producers.wait(None, || {
+            if self.chan.receivers_gone() {
+                Some(false)
+            } else {
+                queue.try_reserve(1).then_some(true)
+            }
+        });
+
+        if connected != Some(true) {
+            return Err(SendError(t));
+        }
+        queue.link_back(t);
+        Ok(())
+    }
+
+    /// Sends a message if there is room for it right away.
+    pub fn try_send(&self, t: T) -> Result<(), TrySendError<T>> {
+        if self.chan.receivers_gone() {
+            return Err(TrySendError::Disconnected(t));
+        }
+        self.chan
+            .queue
+            .try_push_back(t)
+            .map_err(|Full(t)| TrySendError::Full(t))
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.chan.senders.fetch_add(1, Ordering::Relaxed);
+        Self {
+            chan: self.chan.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        if self.chan.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.chan.queue.consumers.notify_all();
+        }
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+pub struct Receiver<T> {
+    chan: Arc<Chan<T>>,
+}
+
+impl<T> Receiver<T> {
+    /// Receives a message, parking the thread until one is available.
+    ///
+    /// Fails once every sender is dropped and the channel is drained.
+    pub fn recv(&self) -> Result<T, Disconnected> {
+        let queue = &self.chan.queue;
+        queue
+            .consumers
+            .wait(None, || match self.try_recv() {
+                Ok(t) => Some(Ok(t)),
+                Err(TryRecvError::Disconnected) => Some(Err(Disconnected)),
+                Err(TryRecvError::Empty) => None,
+            })
+            .unwrap()
+    }
+
+    /// Receives a message if one is available right away.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        // every message was pushed before the last sender went away, so
+        // checking first means an empty queue afterwards really is drained
+        let disconnected = self.chan.senders_gone();
+        match self.chan.queue.pop_front() {
+            Some(t) => Ok(t),
+            None if disconnected => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        self.chan.receivers.fetch_add(1, Ordering::Relaxed);
+        Self {
+            chan: self.chan.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        if self.chan.receivers.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.chan.queue.producers.notify_all();
+        }
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+/// The error returned by [`Receiver::recv`] once every sender is dropped and
+/// the channel is drained.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Disconnected;
+
+impl fmt::Display for Disconnected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("channel is disconnected")
+    }
+}
+
+impl error::Error for Disconnected {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    Disconnected,
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("channel is empty"),
+            Self::Disconnected => Disconnected.fmt(f),
+        }
+    }
+}
+
+impl error::Error for TryRecvError {}
+
+/// The error returned by [`Sender::send`] once every receiver is dropped,
+/// carrying the message that could not be sent.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+impl<T> SendError<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Disconnected.fmt(f)
+    }
+}
+
+impl<T> error::Error for SendError<T> {}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    Full(T),
+    Disconnected(T),
+}
+
+impl<T> TrySendError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            Self::Full(t) | Self::Disconnected(t) => t,
+        }
+    }
+}
+
+impl<T> fmt::Debug for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full(_) => f.write_str("Full(..)"),
+            Self::Disconnected(_) => f.write_str("Disconnected(..)"),
+        }
+    }
+}
+
+impl<T> fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full(_) => f.write_str("channel is full"),
+            Self::Disconnected(_) => Disconnected.fmt(f),
+        }
+    }
+}
+
+impl<T> error::Error for TrySendError<T> {}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Barrier, thread, time::Duration};
+
+    use super::*;
+
+    #[test]
+    fn send_recv() {
+        let (tx, rx) = unbounded();
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert_eq!(rx.recv(), Ok(1));
+        assert_eq!(rx.try_recv(), Ok(2));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[test]
+    fn drain_after_senders_drop() {
+        let (tx, rx) = unbounded();
+        let tx2 = tx.clone();
+        tx.send(1).unwrap();
+        tx2.send(2).unwrap();
+        drop(tx);
+        assert_eq!(rx.try_recv(), Ok(1));
+        drop(tx2);
+
+        assert_eq!(rx.recv(), Ok(2));
+        assert_eq!(rx.recv(), Err(Disconnected));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn last_sender_drop_wakes_receivers() {
+        let (tx, rx) = unbounded::<u32>();
+        thread::scope(|s| {
+            for _ in 0..4 {
+                let rx = rx.clone();
+                s.spawn(move || assert_eq!(rx.recv(), Err(Disconnected)));
+            }
+            thread::sleep(Duration::from_millis(10));
+            drop(tx);
+        });
+    }
+
+    #[test]
+    fn send_after_receivers_drop() {
+        let (tx, rx) = bounded(1);
+        tx.send(1).unwrap();
+        assert_eq!(tx.try_send(2), Err(TrySendError::Full(2)));
+
+        thread::scope(|s| {
+            s.spawn(|| assert_eq!(tx.send(3), Err(SendError(3))));
+            thread::sleep(Duration::from_millis(10));
+            drop(rx);
+        });
+        assert_eq!(tx.try_send(4), Err(TrySendError::Disconnected(4)));
+    }
+
+    const THREADS: usize = 4;
+    const ITER: usize = if cfg!(miri) { 20 } else { 1000 };
+
+    #[test]
+    #[should_panic = "a bounded channel needs a capacity of at least 1"]
+    fn bounded_zero() {
+        let _ = bounded::<u8>(0);
+    }
+
+    #[test]
+    fn send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Sender<std::cell::Cell<u8>>>();
+        assert_send_sync::<Receiver<std::cell::Cell<u8>>>();
+    }
+
+    #[test]
+    fn bounded_multi() {
+        let (tx, rx) = bounded(8);
+        let b = Barrier::new(THREADS * 2);
+        let sum = thread::scope(|s| {
+            for t in 0..THREADS {
+                let (tx, b) = (tx.clone(), &b);
+                s.spawn(move || {
+                    b.wait();
+                    for i in (t..ITER).step_by(THREADS) {
+                        tx.send(i).unwrap();
+                    }
+                });
+            }
+            drop(tx);
+
+            let handles: Vec<_> = (0..THREADS)
+                .map(|_| {
+                    let (rx, b) = (rx.clone(), &b);
+                    s.spawn(move || {
+                        b.wait();
+                        let mut sum = 0;
+                        while let Ok(i) = rx.recv() {
+                            sum += i;
+                        }
+                        sum
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap())
+                .sum::<usize>()
+        });
+        assert_eq!(sum, (0..ITER).sum());
+    }
+}
@@ -1,392 +1,932 @@
-use seize::{reclaim, AtomicPtr, Collector, Guard, Linked};
-use std::{mem::ManuallyDrop, ptr};
-use std::{
-    mem::MaybeUninit,
-    sync::atomic::{AtomicUsize, Ordering},
-};
-
-pub struct LinkedList<T> {
-    head: AtomicPtr<Node<T>>,
-    tail: AtomicPtr<Node<T>>,
+use crate::assert_guard;
+use crate::builder::{Build, Builder};
+#[cfg(feature = "instrument")]
+use crate::instrument::Hook;
+use crate::reclaim::{Collector, DefaultReclaimer, Reclaimer, SeizeReclaimer};
+use crate::slot::Slot;
+use crate::sync::{AtomicPtr, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::{fmt, ptr};
+
+// A lock-free deque after Michael, "CAS-Based Lock-Free Algorithm for Shared
+// Deques" (2003). Both ends and a status word live in an immutable `Anchor`
+// that is swapped with a single CAS, standing in for the paper's double-width
+// CAS. A push swings the anchor to the new node in an unstable state, after
+// which any thread can finish linking the old end node to it.
+pub struct LinkedList<T, R: Reclaimer = DefaultReclaimer> {
+    anchor: AtomicPtr<R::Linked<Anchor<T, R>>>,
+    // counts a push before its node is linked, so that the pop of that node
+    // never takes it below zero
     len: AtomicUsize,
-    collector: Collector,
+    reclaimer: R,
+    #[cfg(feature = "instrument")]
+    hook: Option<Arc<dyn Hook>>,
 }
 
+unsafe impl<T: Send, R: Reclaimer> Send for LinkedList<T, R> {}
+unsafe impl<T: Send + Sync, R: Reclaimer> Sync for LinkedList<T, R> {}
+
+type NodePtr<T, R> = *mut <R as Reclaimer>::Linked<Node<T, R>>;
+type AnchorPtr<T, R> = *mut <R as Reclaimer>::Linked<Anchor<T, R>>;
+
 #[derive(Debug)]
-pub struct Node<T> {
-    inner: MaybeUninit<ManuallyDrop<T>>,
-    next: AtomicPtr<Node<T>>,
-    prev: AtomicPtr<Node<T>>,
+pub struct Node<T, R: Reclaimer> {
+    inner: Slot<T>,
+    next: AtomicPtr<R::Linked<Node<T, R>>>,
+    prev: AtomicPtr<R::Linked<Node<T, R>>>,
 }
 
-impl<T> Node<T> {
+impl<T, R: Reclaimer> Node<T, R> {
     fn new(t: T) -> Self {
         Self {
-            inner: MaybeUninit::new(ManuallyDrop::new(t)),
+            inner: Slot::new(t),
             next: AtomicPtr::new(ptr::null_mut()),
             prev: AtomicPtr::new(ptr::null_mut()),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Stable,
+    // `head.next.prev` may not point at `head` yet
+    PushFront,
+    // `tail.prev.next` may not point at `tail` yet
+    PushBack,
+}
+
+struct Anchor<T, R: Reclaimer> {
+    head: NodePtr<T, R>,
+    tail: NodePtr<T, R>,
+    status: Status,
+}
+
+impl<T, R: Reclaimer> Clone for Anchor<T, R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, R: Reclaimer> Copy for Anchor<T, R> {}
+
+impl<T, R: Reclaimer> Anchor<T, R> {
+    const EMPTY: Self = Self {
+        head: ptr::null_mut(),
+        tail: ptr::null_mut(),
+        status: Status::Stable,
+    };
+}
+
 impl<T> LinkedList<T> {
     pub fn new() -> Self {
-        let list = Self {
-            head: AtomicPtr::new(ptr::null_mut()),
-            tail: AtomicPtr::new(ptr::null_mut()),
-            collector: Collector::new(),
-            len: AtomicUsize::new(0),
-        };
-
-        let sentinel = list.collector.link_boxed(Node {
-            inner: MaybeUninit::uninit(),
-            next: AtomicPtr::new(ptr::null_mut()),
-            prev: AtomicPtr::new(ptr::null_mut()),
-        });
+        Self::with_reclaimer(DefaultReclaimer::new())
+    }
 
-        list.head.store(sentinel, Ordering::Relaxed);
-        list.tail.store(sentinel, Ordering::Relaxed);
+    /// Creates a list that retires its nodes into `collector`, which may be
+    /// shared with other containers.
+    pub fn with_collector(collector: Arc<Collector>) -> Self {
+        Self::with_reclaimer(SeizeReclaimer::shared(collector))
+    }
+}
 
+impl<T> Build for LinkedList<T> {
+    fn from_builder(builder: Builder) -> Self {
+        builder.unbounded();
+        #[allow(unused_mut)]
+        let mut list = Self::with_reclaimer(builder.reclaimer());
+        // every push also swings the anchor to a new one
+        list.reclaimer
+            .preallocate::<Node<T, SeizeReclaimer>>(builder.preallocate);
+        list.reclaimer
+            .preallocate::<Anchor<T, SeizeReclaimer>>(builder.preallocate);
+        #[cfg(feature = "instrument")]
+        {
+            list.hook = builder.hook;
+        }
         list
     }
+}
+
+impl<T, R: Reclaimer> LinkedList<T, R> {
+    pub fn with_reclaimer(reclaimer: R) -> Self {
+        let anchor = reclaimer.link_boxed(Anchor::EMPTY);
 
+        Self {
+            anchor: AtomicPtr::new(anchor),
+            len: AtomicUsize::new(0),
+            reclaimer,
+            #[cfg(feature = "instrument")]
+            hook: None,
+        }
+    }
+
+    #[deprecated(
+        note = "the count can be off while operations are in progress, use `approximate_len`"
+    )]
     pub fn len(&self) -> usize {
+        self.approximate_len()
+    }
+
+    /// Returns the number of elements, counting a push from before its node
+    /// is linked and a pop until after its node is unlinked.
+    ///
+    /// The count is at least the length at the moment it is read, and at
+    /// most that plus the number of pushes and pops in progress. It is exact
+    /// while no operation is.
+    pub fn approximate_len(&self) -> usize {
         self.len.load(Ordering::Acquire)
     }
 
+    /// Returns whether the list has no elements, as of reading the anchor.
+    pub fn is_empty(&self) -> bool {
+        let guard = self.reclaimer.enter();
+        let anchor = R::protect(&guard, &self.anchor, Ordering::Acquire);
+        unsafe { &*anchor }.head.is_null()
+    }
+
+    /// Enters the list's reclaimer, for use with [`LinkedList::iter`] and the
+    /// `*_with` methods.
+    pub fn guard(&self) -> R::Guard<'_> {
+        self.reclaimer.enter()
+    }
+
+    /// Iterates over clones of a snapshot of the elements from front to back.
+    ///
+    /// Nodes stay allocated for as long as `guard` is held, elements pushed or
+    /// popped concurrently may or may not be observed. The elements are
+    /// cloned because a pop may move them out at any time, use
+    /// [`Iter::next_with`] to look at them in place, which does not need
+    /// `T: Clone`. A pop of an element that is being cloned or looked at
+    /// spins until that is done.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this list's reclaimer.
+    pub fn iter<'g>(&'g self, guard: &'g R::Guard<'_>) -> Iter<'g, T, R> {
+        assert_guard(guard, &self.reclaimer);
+
+        let anchor = unsafe { **R::protect(guard, &self.anchor, Ordering::Acquire) };
+        let before_tail = match anchor.status {
+            Status::PushBack => {
+                R::protect(guard, &unsafe { &*anchor.tail }.prev, Ordering::Acquire)
+            }
+            _ => ptr::null_mut(),
+        };
+
+        Iter {
+            current: anchor.head,
+            tail: anchor.tail,
+            before_tail,
+            guard: R::reborrow(guard),
+        }
+    }
+
+    /// Returns a clone of the element at the front without removing it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this list's reclaimer.
+    pub fn front(&self, guard: &R::Guard<'_>) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.front_with(guard, T::clone)
+    }
+
+    /// Calls `f` with the element at the front without removing it, and
+    /// returns what it returns. Unlike [`LinkedList::front`] this does not need
+    /// `T: Clone`.
+    ///
+    /// A pop of the element on another thread waits for `f` to return, and
+    /// `f` must not pop from the list itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this list's reclaimer, or if `f`
+    /// pops the element it is given.
+    pub fn front_with<U>(&self, guard: &R::Guard<'_>, f: impl FnOnce(&T) -> U) -> Option<U> {
+        self.iter(guard).next_with(f)
+    }
+
+    /// Returns a clone of the element at the back without removing it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this list's reclaimer.
+    pub fn back(&self, guard: &R::Guard<'_>) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.back_with(guard, T::clone)
+    }
+
+    /// Calls `f` with the element at the back without removing it, and
+    /// returns what it returns. Unlike [`LinkedList::back`] this does not need
+    /// `T: Clone`.
+    ///
+    /// A pop of the element on another thread waits for `f` to return, and
+    /// `f` must not pop from the list itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this list's reclaimer, or if `f`
+    /// pops the element it is given.
+    pub fn back_with<U>(&self, guard: &R::Guard<'_>, f: impl FnOnce(&T) -> U) -> Option<U> {
+        assert_guard(guard, &self.reclaimer);
+
+        let mut f = Some(f);
+        loop {
+            let anchor = unsafe { **R::protect(guard, &self.anchor, Ordering::Acquire) };
+            if anchor.tail.is_null() {
+                return None;
+            }
+            // a pop swings the anchor before taking the value, so look again
+            if let Some(u) = unsafe { &*anchor.tail }
+                .inner
+                .read(|t| f.take().unwrap()(t))
+            {
+                return Some(u);
+            }
+        }
+    }
+
+    /// Returns the elements front to back, after checking that every node
+    /// links back to the one before it. For looking at the structure of the
+    /// list in tests, so only available in debug builds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the links are inconsistent, beyond the one a push that was
+    /// not stabilized yet leaves behind.
+    #[cfg(any(test, debug_assertions))]
+    pub fn debug_nodes(&mut self) -> Vec<&T> {
+        let anchor = unsafe { **self.anchor.load(Ordering::Relaxed) };
+        let before_tail = match anchor.status {
+            Status::PushBack => unsafe { &*anchor.tail }.prev.load(Ordering::Relaxed),
+            _ => ptr::null_mut(),
+        };
+
+        let mut nodes = Vec::new();
+        let (mut prev, mut current) = (ptr::null_mut(), anchor.head);
+        while !current.is_null() {
+            let node = unsafe { &*current };
+            // the head's `prev` is left at whatever was popped before it
+            let lagging = anchor.status == Status::PushFront && prev == anchor.head;
+            assert!(
+                prev.is_null() || lagging || node.prev.load(Ordering::Relaxed) == prev,
+                "element {} does not link back to the one before it",
+                nodes.len()
+            );
+            nodes.push(unsafe { node.inner.get() });
+
+            prev = current;
+            current = if current == anchor.tail {
+                ptr::null_mut()
+            } else if current == before_tail {
+                anchor.tail
+            } else {
+                node.next.load(Ordering::Relaxed)
+            };
+        }
+        assert_eq!(prev, anchor.tail, "the last element is not the tail");
+        nodes
+    }
+
+    /// Tries to swing the anchor from `current` to a copy of `anchor`,
+    /// retiring `current` on success. The allocation for the new anchor is
+    /// kept in `spare` and reused across failed attempts.
     #[inline]
-    fn push_back_internal(
+    fn swap_anchor(
         &self,
-        onto: *mut Linked<Node<T>>,
-        new: *mut Linked<Node<T>>,
-        guard: &Guard,
-    ) -> bool {
-        let next = guard.protect(&unsafe { &*onto }.next, Ordering::Acquire);
-        unsafe { &*new }.prev.store(onto, Ordering::Release);
-
-        if !next.is_null() {
-            let _ = self
-                .tail
-                .compare_exchange(onto, next, Ordering::Acquire, Ordering::Relaxed);
+        current: AnchorPtr<T, R>,
+        anchor: Anchor<T, R>,
+        spare: &mut Option<AnchorPtr<T, R>>,
+    ) -> Option<AnchorPtr<T, R>> {
+        let new = *spare.get_or_insert_with(|| self.reclaimer.link_boxed(anchor));
+        unsafe { **new = anchor };
 
-            false
-        } else {
-            let result = unsafe { &*onto }
-                .next
-                .compare_exchange(ptr::null_mut(), new, Ordering::Release, Ordering::Relaxed)
-                .is_ok();
+        match self
+            .anchor
+            .compare_exchange(current, new, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => {
+                *spare = None;
+                unsafe { self.reclaimer.retire(current) };
+                Some(new)
+            }
+            Err(_) => {
+                instrument!(self, CasLost);
+                None
+            }
+        }
+    }
 
-            if result {
-                let _ = self
-                    .tail
-                    .compare_exchange(onto, new, Ordering::Release, Ordering::Relaxed);
+    #[inline]
+    fn stabilize(
+        &self,
+        current: AnchorPtr<T, R>,
+        spare: &mut Option<AnchorPtr<T, R>>,
+        guard: &R::Guard<'_>,
+    ) {
+        match unsafe { &*current }.status {
+            Status::PushBack => {
+                instrument!(self, HelpedStabilize);
+                self.stabilize_back(current, spare, guard)
+            }
+            Status::PushFront => {
+                instrument!(self, HelpedStabilize);
+                self.stabilize_front(current, spare, guard)
             }
-            result
+            Status::Stable => {}
         }
     }
 
     #[inline]
-    fn push_front_internal(
+    fn stabilize_back(
         &self,
-        onto: *mut Linked<Node<T>>,
-        new: *mut Linked<Node<T>>,
-        guard: &Guard,
-    ) -> bool {
-        let prev = guard.protect(&unsafe { &*onto }.prev, Ordering::Acquire);
-        unsafe { &*new }.next.store(onto, Ordering::Release);
+        current: AnchorPtr<T, R>,
+        spare: &mut Option<AnchorPtr<T, R>>,
+        guard: &R::Guard<'_>,
+    ) {
+        let anchor = unsafe { **current };
+        let prev = R::protect(guard, &unsafe { &*anchor.tail }.prev, Ordering::Acquire);
+        if self.anchor.load(Ordering::Acquire) != current {
+            return;
+        }
 
-        if !prev.is_null() {
-            let _ = self
-                .head
-                .compare_exchange(onto, prev, Ordering::Acquire, Ordering::Relaxed);
+        // store the link even if it already compares equal, it may still be
+        // the one to a popped node whose address was reused for the tail
+        let prev_next = R::protect(guard, &unsafe { &*prev }.next, Ordering::Acquire);
+        if self.anchor.load(Ordering::Acquire) != current
+            || unsafe { &*prev }
+                .next
+                .compare_exchange(prev_next, anchor.tail, Ordering::Release, Ordering::Relaxed)
+                .is_err()
+        {
+            return;
+        }
 
-            false
-        } else {
-            let result = unsafe { &*onto }
+        let anchor = Anchor {
+            status: Status::Stable,
+            ..anchor
+        };
+        self.swap_anchor(current, anchor, spare);
+    }
+
+    #[inline]
+    fn stabilize_front(
+        &self,
+        current: AnchorPtr<T, R>,
+        spare: &mut Option<AnchorPtr<T, R>>,
+        guard: &R::Guard<'_>,
+    ) {
+        let anchor = unsafe { **current };
+        let next = R::protect(guard, &unsafe { &*anchor.head }.next, Ordering::Acquire);
+        if self.anchor.load(Ordering::Acquire) != current {
+            return;
+        }
+
+        // same as in `stabilize_back`
+        let next_prev = R::protect(guard, &unsafe { &*next }.prev, Ordering::Acquire);
+        if self.anchor.load(Ordering::Acquire) != current
+            || unsafe { &*next }
                 .prev
-                .compare_exchange(ptr::null_mut(), new, Ordering::Release, Ordering::Relaxed)
-                .is_ok();
-            if result {
-                println!("tail: {}", self.tail.load(Ordering::Acquire) == onto);
-                let _ = self
-                    .head
-                    .compare_exchange(onto, new, Ordering::Release, Ordering::Relaxed);
-            }
-            result
+                .compare_exchange(next_prev, anchor.head, Ordering::Release, Ordering::Relaxed)
+                .is_err()
+        {
+            return;
         }
+
+        let anchor = Anchor {
+            status: Status::Stable,
+            ..anchor
+        };
+        self.swap_anchor(current, anchor, spare);
     }
 
     #[inline]
-    fn pop_front_internal(&self, guard: &Guard) -> Result<Option<T>, ()> {
-        let head = guard.protect(&self.head, Ordering::Acquire);
-        let next = guard.protect(&unsafe { &*head }.next, Ordering::Acquire);
-
-        if !next.is_null() {
-            match self
-                .head
-                .compare_exchange(head, next, Ordering::Release, Ordering::Relaxed)
-            {
-                Ok(_) => {
-                    let tail = guard.protect(&self.tail, Ordering::Release);
-                    if head == tail {
-                        let _ = self.tail.compare_exchange(
-                            tail,
-                            next,
-                            Ordering::Release,
-                            Ordering::Relaxed,
-                        );
-                    }
+    fn push_back_internal(
+        &self,
+        current: AnchorPtr<T, R>,
+        new: NodePtr<T, R>,
+        spare: &mut Option<AnchorPtr<T, R>>,
+        guard: &R::Guard<'_>,
+    ) -> bool {
+        let anchor = unsafe { **current };
+
+        if anchor.tail.is_null() {
+            let anchor = Anchor {
+                head: new,
+                tail: new,
+                status: Status::Stable,
+            };
+            self.swap_anchor(current, anchor, spare).is_some()
+        } else if anchor.status == Status::Stable {
+            unsafe { &*new }.prev.store(anchor.tail, Ordering::Release);
+            let anchor = Anchor {
+                tail: new,
+                status: Status::PushBack,
+                ..anchor
+            };
 
-                    let data = unsafe { ptr::read(&(*next).inner) };
-                    Ok(unsafe { self.consume_and_retire(head, data) })
+            match self.swap_anchor(current, anchor, spare) {
+                Some(published) => {
+                    self.stabilize_back(published, spare, guard);
+                    true
                 }
-                Err(_) => Err(()),
+                None => false,
             }
         } else {
-            Ok(None)
+            self.stabilize(current, spare, guard);
+            false
         }
     }
 
     #[inline]
-    fn pop_back_internal(&self, guard: &Guard) -> Result<Option<T>, ()> {
-        let tail = guard.protect(&self.tail, Ordering::Acquire);
-        let prev = guard.protect(&unsafe { &*tail }.prev, Ordering::Acquire);
-
-        if !prev.is_null() {
-            match self
-                .tail
-                .compare_exchange(tail, prev, Ordering::Release, Ordering::Relaxed)
-            {
-                Ok(_) => {
-                    let head = guard.protect(&self.head, Ordering::Release);
-                    if head == tail {
-                        let _ = self.head.compare_exchange(
-                            head,
-                            prev,
-                            Ordering::Release,
-                            Ordering::Relaxed,
-                        );
-                    }
+    fn push_front_internal(
+        &self,
+        current: AnchorPtr<T, R>,
+        new: NodePtr<T, R>,
+        spare: &mut Option<AnchorPtr<T, R>>,
+        guard: &R::Guard<'_>,
+    ) -> bool {
+        let anchor = unsafe { **current };
+
+        if anchor.head.is_null() {
+            let anchor = Anchor {
+                head: new,
+                tail: new,
+                status: Status::Stable,
+            };
+            self.swap_anchor(current, anchor, spare).is_some()
+        } else if anchor.status == Status::Stable {
+            unsafe { &*new }.next.store(anchor.head, Ordering::Release);
+            let anchor = Anchor {
+                head: new,
+                status: Status::PushFront,
+                ..anchor
+            };
 
-                    let data = unsafe { ptr::read(&(*prev).inner) };
-                    Ok(unsafe { self.consume_and_retire(tail, data) })
+            match self.swap_anchor(current, anchor, spare) {
+                Some(published) => {
+                    self.stabilize_front(published, spare, guard);
+                    true
                 }
-                Err(_) => Err(()),
+                None => false,
             }
         } else {
-            Ok(None)
+            self.stabilize(current, spare, guard);
+            false
         }
     }
 
-    pub fn pop_front(&self) -> Option<T> {
-        let guard = self.collector.enter();
-        loop {
-            if let Ok(head) = self.pop_front_internal(&guard) {
-                return head;
+    #[inline]
+    fn pop_front_internal(
+        &self,
+        spare: &mut Option<AnchorPtr<T, R>>,
+        guard: &R::Guard<'_>,
+        predicate: Option<&mut (dyn FnMut(&T) -> bool + '_)>,
+    ) -> Result<Option<T>, ()> {
+        let current = R::protect(guard, &self.anchor, Ordering::Acquire);
+        let anchor = unsafe { **current };
+
+        if anchor.head.is_null() {
+            return Ok(None);
+        }
+
+        if anchor.head != anchor.tail && anchor.status != Status::Stable {
+            self.stabilize(current, spare, guard);
+            return Err(());
+        }
+
+        // a pop that swung the anchor would wait for the predicate before
+        // taking the value
+        if let Some(predicate) = predicate {
+            match unsafe { &*anchor.head }.inner.read(|t| predicate(t)) {
+                Some(true) => {}
+                Some(false) => return Ok(None),
+                None => return Err(()),
             }
         }
+
+        let popped = if anchor.head == anchor.tail {
+            self.swap_anchor(current, Anchor::EMPTY, spare).is_some()
+        } else {
+            let next = R::protect(guard, &unsafe { &*anchor.head }.next, Ordering::Acquire);
+            let anchor = Anchor {
+                head: next,
+                ..anchor
+            };
+            self.swap_anchor(current, anchor, spare).is_some()
+        };
+
+        if popped {
+            Ok(unsafe { self.consume_and_retire(anchor.head) })
+        } else {
+            Err(())
+        }
     }
 
-    pub fn pop_back(&self) -> Option<T> {
-        let guard = self.collector.enter();
-        loop {
-            if let Ok(tail) = self.pop_back_internal(&guard) {
-                return tail;
-            }
+    #[inline]
+    fn pop_back_internal(
+        &self,
+        spare: &mut Option<AnchorPtr<T, R>>,
+        guard: &R::Guard<'_>,
+    ) -> Result<Option<T>, ()> {
+        let current = R::protect(guard, &self.anchor, Ordering::Acquire);
+        let anchor = unsafe { **current };
+
+        if anchor.tail.is_null() {
+            return Ok(None);
+        }
+
+        let popped = if anchor.head == anchor.tail {
+            self.swap_anchor(current, Anchor::EMPTY, spare).is_some()
+        } else if anchor.status == Status::Stable {
+            let prev = R::protect(guard, &unsafe { &*anchor.tail }.prev, Ordering::Acquire);
+            let anchor = Anchor {
+                tail: prev,
+                ..anchor
+            };
+            self.swap_anchor(current, anchor, spare).is_some()
+        } else {
+            self.stabilize(current, spare, guard);
+            false
+        };
+
+        if popped {
+            Ok(unsafe { self.consume_and_retire(anchor.tail) })
+        } else {
+            Err(())
         }
     }
 
+    /// Removes the front element.
+    ///
+    /// While another thread looks at the element through an iterator or a
+    /// peek, this spins until it is done.
+    pub fn pop_front(&self) -> Option<T> {
+        self.pop_front_with(&self.reclaimer.enter())
+    }
+
+    /// Removes the front element only if `predicate` returns `true` for it.
+    ///
+    /// The predicate may be called more than once if the front changes
+    /// concurrently, the element it last accepted is the one returned. Pops
+    /// of the element it is looking at stall on other threads until it
+    /// returns, and it must not pop from this list itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the predicate pops the element it is looking at, which would
+    /// otherwise wait for the predicate forever. That element is leaked.
+    pub fn pop_front_if(&self, predicate: impl FnMut(&T) -> bool) -> Option<T> {
+        self.pop_front_if_with(predicate, &self.reclaimer.enter())
+    }
+
+    /// Removes the back element.
+    ///
+    /// While another thread looks at the element through an iterator or a
+    /// peek, this spins until it is done.
+    pub fn pop_back(&self) -> Option<T> {
+        self.pop_back_with(&self.reclaimer.enter())
+    }
+
     #[inline]
     pub fn push_back(&self, t: T) {
-        let guard = self.collector.enter();
-        let new = self.collector.link_boxed(Node::new(t));
+        self.push_back_with(t, &self.reclaimer.enter());
+    }
+
+    #[inline]
+    pub fn push_front(&self, t: T) {
+        self.push_front_with(t, &self.reclaimer.enter());
+    }
+
+    /// Like [`LinkedList::pop_front`], under a guard the caller already holds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this list's reclaimer.
+    pub fn pop_front_with(&self, guard: &R::Guard<'_>) -> Option<T> {
+        self.pop_front_matching(None, guard)
+    }
+
+    /// Like [`LinkedList::pop_front_if`], under a guard the caller already
+    /// holds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this list's reclaimer.
+    pub fn pop_front_if_with(
+        &self,
+        mut predicate: impl FnMut(&T) -> bool,
+        guard: &R::Guard<'_>,
+    ) -> Option<T> {
+        self.pop_front_matching(Some(&mut predicate), guard)
+    }
+
+    // Without a predicate any element matches, and the value is not read
+    // before the anchor is swung.
+    fn pop_front_matching(
+        &self,
+        mut predicate: Option<&mut dyn FnMut(&T) -> bool>,
+        guard: &R::Guard<'_>,
+    ) -> Option<T> {
+        assert_guard(guard, &self.reclaimer);
+        let mut spare = None;
+        let result = loop {
+            let predicate = predicate.as_deref_mut();
+            if let Ok(head) = self.pop_front_internal(&mut spare, guard, predicate) {
+                break head;
+            }
+        };
+        self.free_anchor(spare);
+        result
+    }
+
+    /// Like [`LinkedList::pop_back`], under a guard the caller already holds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this list's reclaimer.
+    pub fn pop_back_with(&self, guard: &R::Guard<'_>) -> Option<T> {
+        assert_guard(guard, &self.reclaimer);
+        let mut spare = None;
+        let result = loop {
+            if let Ok(tail) = self.pop_back_internal(&mut spare, guard) {
+                break tail;
+            }
+        };
+        self.free_anchor(spare);
+        result
+    }
+
+    /// Like [`LinkedList::push_back`], under a guard the caller already holds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this list's reclaimer.
+    #[inline]
+    pub fn push_back_with(&self, t: T, guard: &R::Guard<'_>) {
+        assert_guard(guard, &self.reclaimer);
+        let new = self.reclaimer.link_boxed(Node::new(t));
+        let mut spare = None;
+        self.len.fetch_add(1, Ordering::Release);
         loop {
-            let tail = guard.protect(&self.tail, Ordering::Acquire);
-            if self.push_back_internal(tail, new, &guard) {
-                self.len.fetch_add(1, Ordering::Release);
+            let current = R::protect(guard, &self.anchor, Ordering::Acquire);
+            if self.push_back_internal(current, new, &mut spare, guard) {
                 break;
             }
         }
+        self.free_anchor(spare);
     }
 
+    /// Like [`LinkedList::push_front`], under a guard the caller already holds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this list's reclaimer.
     #[inline]
-    pub fn push_front(&self, t: T) {
-        let guard = self.collector.enter();
-        let new = self.collector.link_boxed(Node::new(t));
+    pub fn push_front_with(&self, t: T, guard: &R::Guard<'_>) {
+        assert_guard(guard, &self.reclaimer);
+        let new = self.reclaimer.link_boxed(Node::new(t));
+        let mut spare = None;
+        self.len.fetch_add(1, Ordering::Release);
         loop {
-            let head = guard.protect(&self.head, Ordering::Acquire);
-            if self.push_front_internal(head, new, &guard) {
-                self.len.fetch_add(1, Ordering::Release);
+            let current = R::protect(guard, &self.anchor, Ordering::Acquire);
+            if self.push_front_internal(current, new, &mut spare, guard) {
                 break;
             }
         }
+        self.free_anchor(spare);
     }
 
+    /// Frees an anchor that was allocated for a failed CAS and never published.
     #[inline]
-    unsafe fn consume_and_retire(
-        &self,
-        ptr: *mut Linked<Node<T>>,
-        data: MaybeUninit<ManuallyDrop<T>>,
-    ) -> Option<T> {
-        self.collector.retire(ptr, reclaim::boxed::<Node<T>>);
+    fn free_anchor(&self, anchor: Option<AnchorPtr<T, R>>) {
+        if let Some(anchor) = anchor {
+            unsafe { self.reclaimer.free(anchor) };
+        }
+    }
+
+    /// Takes the value out of a node that was just unlinked and retires it.
+    #[inline]
+    unsafe fn consume_and_retire(&self, ptr: NodePtr<T, R>) -> Option<T> {
+        let data = (&*ptr).inner.take();
+        self.reclaimer.retire(ptr);
         self.len.fetch_sub(1, Ordering::Release);
-        return Some(ManuallyDrop::into_inner(data.assume_init()));
+        Some(data)
+    }
+}
+
+impl<T> Default for LinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<T> for LinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T, R: Reclaimer> Extend<T> for LinkedList<T, R> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for t in iter {
+            self.push_back(t);
+        }
+    }
+}
+
+impl<T, R: Reclaimer> IntoIterator for LinkedList<T, R> {
+    type Item = T;
+    type IntoIter = IntoIter<T, R>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+/// An owning iterator that pops elements from the front.
+pub struct IntoIter<T, R: Reclaimer = DefaultReclaimer>(LinkedList<T, R>);
+
+impl<T, R: Reclaimer> Iterator for IntoIter<T, R> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_front()
+    }
+}
+
+impl<T, R: Reclaimer> DoubleEndedIterator for IntoIter<T, R> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_back()
+    }
+}
+
+impl<T: fmt::Debug, R: Reclaimer> fmt::Debug for LinkedList<T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let guard = self.guard();
+        let (mut iter, mut list) = (self.iter(&guard), f.debug_list());
+        // formatted in place, a pop of an element waits for it to be done
+        while iter
+            .next_with(|t| {
+                list.entry(t);
+            })
+            .is_some()
+        {}
+        list.finish()
+    }
+}
+
+impl<T, R: Reclaimer> Drop for LinkedList<T, R> {
+    fn drop(&mut self) {
+        let anchor_ptr = self.anchor.load(Ordering::Relaxed);
+        let anchor = unsafe { **anchor_ptr };
+        unsafe { self.reclaimer.free(anchor_ptr) };
+
+        // a push that was never stabilized may leave the link into `tail`
+        // pointing at a node that was already popped, so jump over it
+        let before_tail = match anchor.status {
+            Status::PushBack => unsafe { &*anchor.tail }.prev.load(Ordering::Relaxed),
+            _ => ptr::null_mut(),
+        };
+
+        let mut current = anchor.head;
+        while !current.is_null() {
+            let node = unsafe { &mut **current };
+            unsafe { node.inner.drop_value() };
+
+            let next = if current == anchor.tail {
+                ptr::null_mut()
+            } else if current == before_tail {
+                anchor.tail
+            } else {
+                node.next.load(Ordering::Relaxed)
+            };
+            unsafe { self.reclaimer.free(current) };
+            current = next;
+        }
+
+        // nodes and anchors retired by earlier operations are freed by the
+        // reclaimer, which is dropped right after this
+    }
+}
+
+/// The iterator returned by [`LinkedList::iter`], which only implements
+/// [`Iterator`] for `T: Clone`.
+pub struct Iter<'g, T, R: Reclaimer + 'g = DefaultReclaimer> {
+    current: NodePtr<T, R>,
+    tail: NodePtr<T, R>,
+    before_tail: NodePtr<T, R>,
+    guard: &'g R::Guard<'g>,
+}
+
+impl<'g, T: 'g, R: Reclaimer> Iter<'g, T, R> {
+    fn next_slot(&mut self) -> Option<&'g Slot<T>> {
+        if self.current.is_null() {
+            return None;
+        }
+
+        let node = unsafe { &*self.current };
+        // same as in `Drop`, the link into an unstable tail may be stale
+        self.current = if self.current == self.tail {
+            ptr::null_mut()
+        } else if self.current == self.before_tail {
+            self.tail
+        } else {
+            R::protect(self.guard, &node.next, Ordering::Acquire)
+        };
+
+        Some(&node.inner)
+    }
+
+    /// Calls `f` with the next element in place and returns what it returns,
+    /// which also works for elements that are not `Clone`.
+    ///
+    /// A pop of the element on another thread waits for `f` to return, and
+    /// `f` must not pop from the list itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` pops the element it is given.
+    pub fn next_with<U>(&mut self, f: impl FnOnce(&T) -> U) -> Option<U> {
+        let mut f = Some(f);
+        // elements popped since the snapshot are skipped
+        while let Some(slot) = self.next_slot() {
+            // `read` only calls `f` if the element is still there
+            if let Some(u) = slot.read(|t| f.take().unwrap()(t)) {
+                return Some(u);
+            }
+        }
+        None
+    }
+}
+
+impl<'g, T: Clone + 'g, R: Reclaimer> Iterator for Iter<'g, T, R> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with(T::clone)
     }
 }
-//
-// impl<T> Drop for LinkedList<T> {
-//     fn drop(&mut self) {
-//         while self.pop_front().is_some() {}
-//         while self.pop_back().is_some() {}
-//     }
-// }
 
 #[cfg(test)]
 mod tests {
-    use std::{sync::Barrier, thread, time::Duration};
+    use std::{
+        collections::VecDeque,
+        sync::{
+            atomic::{AtomicU64, AtomicUsize},
+            Barrier, Mutex,
+        },
+        thread,
+    };
 
     use super::*;
+    use crate::testing::{self, DropCount, Rng};
 
     #[test]
     fn push_back_pop_front() {
-        let list = LinkedList::new();
+        let mut list = LinkedList::new();
         list.push_back(1);
-        let head = list.head.load(Ordering::Acquire);
-        let data = unsafe { ptr::read(&(*head).inner) };
-        let head_data = unsafe { ManuallyDrop::into_inner(data.assume_init()) };
-
-        let tail = list.tail.load(Ordering::Acquire);
-        let data = unsafe { ptr::read(&(*tail).inner) };
-        let tail_data = unsafe { ManuallyDrop::into_inner(data.assume_init()) };
-        println!("head: {}, tail: {}", head_data, tail_data);
+        assert_eq!(list.debug_nodes(), [&1]);
         list.push_back(2);
-
-        let head = list.head.load(Ordering::Acquire);
-        let data = unsafe { ptr::read(&(*head).inner) };
-        let head_data = unsafe { ManuallyDrop::into_inner(data.assume_init()) };
-
-        let tail = list.tail.load(Ordering::Acquire);
-        let data = unsafe { ptr::read(&(*tail).inner) };
-        let tail_data = unsafe { ManuallyDrop::into_inner(data.assume_init()) };
-        println!("head: {}, tail: {}", head_data, tail_data);
+        assert_eq!(list.debug_nodes(), [&1, &2]);
         list.push_back(3);
+        assert_eq!(list.debug_nodes(), [&1, &2, &3]);
 
-        let head = list.head.load(Ordering::Acquire);
-        let data = unsafe { ptr::read(&(*head).inner) };
-        let head_data = unsafe { ManuallyDrop::into_inner(data.assume_init()) };
-
-        let tail = list.tail.load(Ordering::Acquire);
-        let data = unsafe { ptr::read(&(*tail).inner) };
-        let tail_data = unsafe { ManuallyDrop::into_inner(data.assume_init()) };
-        println!("head: {}, tail: {}", head_data, tail_data);
-
-        // let head = list.tail.load(Ordering::Acquire);
-        // let data = unsafe { ptr::read(&(*head).inner) };
-        // let data = unsafe { ManuallyDrop::into_inner(data.assume_init()) };
-        // assert_eq!(data, 3);
-        //
-        // let head = list.head.load(Ordering::Acquire);
-        // let head_next = unsafe {
-        //     (&*list.head.load(Ordering::Acquire))
-        //         .next
-        //         .load(Ordering::Acquire)
-        // };
-        //
-        // let head_next_2 = unsafe { (*head_next).next.load(Ordering::Acquire) };
-        // let head_next_3 = unsafe { (*head_next_2).next.load(Ordering::Acquire) };
-        //
-        // assert_eq!(unsafe { (*head_next).prev.load(Ordering::Acquire) }, head);
-        // assert_eq!(
-        //     unsafe { (*head_next_2).prev.load(Ordering::Acquire) },
-        //     head_next
-        // );
-        // assert_eq!(
-        //     unsafe { (*head_next_3).prev.load(Ordering::Acquire) },
-        //     head_next_2
-        // );
-        //
-        // assert_eq!(
-        //     unsafe { (*head_next).next.load(Ordering::Acquire) },
-        //     head_next_2
-        // );
-        // assert_eq!(
-        //     unsafe { (*head_next_2).next.load(Ordering::Acquire) },
-        //     head_next_3
-        // );
-        //
-        assert_eq!(list.len(), 3);
+        assert_eq!(list.approximate_len(), 3);
         assert_eq!(list.pop_front().unwrap(), 1);
         assert_eq!(list.pop_front().unwrap(), 2);
         assert_eq!(list.pop_front().unwrap(), 3);
         assert!(list.pop_front().is_none());
-        assert_eq!(list.len(), 0);
+        assert_eq!(list.approximate_len(), 0);
     }
 
     #[test]
     fn push_front_pop_front() {
-        let list = LinkedList::new();
+        let mut list = LinkedList::new();
 
         list.push_front(1);
-        let head = list.head.load(Ordering::Acquire);
-        let data = unsafe { ptr::read(&(*head).inner) };
-        let data = unsafe { ManuallyDrop::into_inner(data.assume_init()) };
-        assert_eq!(data, 1);
+        assert_eq!(list.debug_nodes(), [&1]);
 
         list.push_front(2);
-        let head = list.head.load(Ordering::Acquire);
-        let data = unsafe { ptr::read(&(*head).inner) };
-        let data = unsafe { ManuallyDrop::into_inner(data.assume_init()) };
-        assert_eq!(data, 2);
+        assert_eq!(list.debug_nodes(), [&2, &1]);
 
         list.push_front(3);
-        let head = list.head.load(Ordering::Acquire);
-        let data = unsafe { ptr::read(&(*head).inner) };
-        let data = unsafe { ManuallyDrop::into_inner(data.assume_init()) };
-        assert_eq!(data, 3);
-
-        let head = list.head.load(Ordering::Acquire);
-        let head_next = unsafe {
-            (&*list.head.load(Ordering::Acquire))
-                .next
-                .load(Ordering::Acquire)
-        };
+        assert_eq!(list.debug_nodes(), [&3, &2, &1]);
+
+        assert_eq!(list.approximate_len(), 3);
 
-        let head_next_2 = unsafe { (*head_next).next.load(Ordering::Acquire) };
-        let head_next_3 = unsafe { (*head_next_2).next.load(Ordering::Acquire) };
-
-        assert_eq!(unsafe { (*head_next).prev.load(Ordering::Acquire) }, head);
-        assert_eq!(
-            unsafe { (*head_next_2).prev.load(Ordering::Acquire) },
-            head_next
-        );
-        assert_eq!(
-            unsafe { (*head_next_3).prev.load(Ordering::Acquire) },
-            head_next_2
-        );
-
-        assert_eq!(
-            unsafe { (*head_next).next.load(Ordering::Acquire) },
-            head_next_2
-        );
-        assert_eq!(
-            unsafe { (*head_next_2).next.load(Ordering::Acquire) },
-            head_next_3
-        );
-
-        assert_eq!(list.len(), 3);
-
-        let head_next = unsafe {
-            (&*list.head.load(Ordering::Acquire))
-                .next
-                .load(Ordering::Acquire)
-        };
-        list.pop_front().unwrap();
-        let head = list.head.load(Ordering::Acquire);
-        assert_eq!(head, head_next);
         assert_eq!(list.pop_front().unwrap(), 3);
+        assert_eq!(list.debug_nodes(), [&2, &1]);
         assert_eq!(list.pop_front().unwrap(), 2);
         assert_eq!(list.pop_front().unwrap(), 1);
-        assert_eq!(list.len(), 0);
+        assert!(list.pop_front().is_none());
+        assert_eq!(list.approximate_len(), 0);
     }
 
     #[test]
@@ -395,15 +935,37 @@ mod tests {
         list.push_back(1);
         list.push_back(2);
         list.push_back(3);
-        assert_eq!(list.len(), 3);
+        assert_eq!(list.approximate_len(), 3);
         assert_eq!(list.pop_back().unwrap(), 3);
         assert_eq!(list.pop_back().unwrap(), 2);
         assert_eq!(list.pop_back().unwrap(), 1);
         assert!(list.pop_back().is_none());
-        assert_eq!(list.len(), 0);
+        assert_eq!(list.approximate_len(), 0);
+    }
+
+    const ITER: u32 = if cfg!(miri) { 20 } else { 100 };
+    const THREADS: usize = 4;
+
+    #[test]
+    fn len() {
+        let list = LinkedList::new();
+        assert!(list.is_empty());
+        list.push_back(1);
+        list.push_back(2);
+        assert_eq!(list.approximate_len(), 2);
+        assert!(!list.is_empty());
+
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.approximate_len(), 1);
+        assert_eq!(list.pop_front(), Some(2));
+        assert_eq!(list.approximate_len(), 0);
+        assert!(list.is_empty());
     }
 
-    const ITER: u32 = 100;
+    #[test]
+    fn len_bounds() {
+        testing::len_bounds(&LinkedList::new());
+    }
 
     #[test]
     fn push_pop_multi() {
@@ -419,13 +981,13 @@ mod tests {
 
             s.spawn(|| {
                 b.wait();
-                thread::sleep(Duration::from_millis(10));
-                for _ in 0..ITER {
-                    let _ = list.pop_front();
+                let mut popped = 0;
+                while popped < ITER {
+                    popped += u32::from(list.pop_front().is_some());
                 }
             });
         });
-        assert_eq!(list.len(), 0);
+        assert_eq!(list.approximate_len(), 0);
     }
 
     #[test]
@@ -445,4 +1007,538 @@ mod tests {
         assert_eq!(list.pop_back().unwrap(), 1);
         assert_eq!(list.pop_back().unwrap(), 2);
     }
+
+    #[test]
+    fn drop_empty() {
+        let drops = AtomicUsize::new(0);
+        let list = LinkedList::new();
+        list.push_back(DropCount(&drops));
+        drop(list.pop_front());
+        assert_eq!(drops.load(Ordering::Relaxed), 1);
+        drop(list);
+        assert_eq!(drops.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn drop_remaining() {
+        let drops = AtomicUsize::new(0);
+        let list = LinkedList::new();
+        for _ in 0..10 {
+            list.push_back(DropCount(&drops));
+        }
+        for _ in 0..3 {
+            drop(list.pop_front());
+        }
+        assert_eq!(drops.load(Ordering::Relaxed), 3);
+        drop(list);
+        assert_eq!(drops.load(Ordering::Relaxed), 10);
+    }
+
+    #[test]
+    fn drop_after_multi() {
+        let drops = AtomicUsize::new(0);
+        let list = LinkedList::new();
+        let b = Barrier::new(2);
+        thread::scope(|s| {
+            s.spawn(|| {
+                b.wait();
+                for _ in 0..ITER {
+                    list.push_back(DropCount(&drops));
+                }
+            });
+
+            s.spawn(|| {
+                b.wait();
+                for _ in 0..ITER / 2 {
+                    drop(list.pop_front());
+                }
+            });
+        });
+        drop(list);
+        assert_eq!(drops.load(Ordering::Relaxed), ITER as usize);
+    }
+
+    #[test]
+    fn iter() {
+        let list = LinkedList::new();
+        let guard = list.guard();
+        assert_eq!(list.iter(&guard).next(), None);
+
+        list.push_back(2);
+        list.push_front(1);
+        list.push_back(3);
+        assert!(list.iter(&guard).eq([1, 2, 3]));
+
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_front(), Some(1));
+        assert!(list.iter(&guard).eq([2]));
+    }
+
+    #[test]
+    fn iter_concurrent_pops() {
+        // the elements own heap memory, which their pop frees right away
+        let list = LinkedList::new();
+        let b = Barrier::new(THREADS);
+        thread::scope(|s| {
+            for t in 0..THREADS - 1 {
+                let (list, b) = (&list, &b);
+                s.spawn(move || {
+                    b.wait();
+                    for i in 0..ITER {
+                        if t % 2 == 0 {
+                            list.push_back(i.to_string());
+                            assert!(list.pop_front().is_some());
+                        } else {
+                            list.push_front(i.to_string());
+                            assert!(list.pop_back().is_some());
+                        }
+                    }
+                });
+            }
+
+            b.wait();
+            for _ in 0..ITER {
+                let guard = list.guard();
+                let ends = list.front(&guard).into_iter().chain(list.back(&guard));
+                for i in list.iter(&guard).chain(ends) {
+                    assert!(i.parse::<u32>().unwrap() < ITER);
+                }
+                assert!(format!("{list:?}").starts_with('['));
+            }
+        });
+    }
+
+    #[test]
+    fn iter_unstable_tail() {
+        let mut list = LinkedList::new();
+        list.push_back(1);
+        list.push_back(2);
+        assert_eq!(list.pop_back(), Some(2));
+
+        // publish a push without linking `tail.prev.next`, leaving it at the
+        // node popped above
+        let guard = list.guard();
+        let current = DefaultReclaimer::protect(&guard, &list.anchor, Ordering::Acquire);
+        let anchor = unsafe { **current };
+        let new = list.reclaimer.link_boxed(Node::new(3));
+        unsafe { &*new }.prev.store(anchor.tail, Ordering::Release);
+        let unstable = Anchor {
+            tail: new,
+            status: Status::PushBack,
+            ..anchor
+        };
+        assert!(list.swap_anchor(current, unstable, &mut None).is_some());
+        list.len.fetch_add(1, Ordering::Release);
+
+        assert!(list.iter(&guard).eq([1, 3]));
+        drop(guard);
+        assert_eq!(list.debug_nodes(), [&1, &3]);
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_front(), Some(3));
+    }
+
+    #[test]
+    fn front_back() {
+        let list = LinkedList::new();
+        let guard = list.guard();
+        assert_eq!(list.front(&guard), None);
+        assert_eq!(list.back(&guard), None);
+
+        list.push_back(1);
+        assert_eq!(list.front(&guard), Some(1));
+        assert_eq!(list.back(&guard), Some(1));
+
+        list.push_back(2);
+        list.push_front(0);
+        assert_eq!(list.front(&guard), Some(0));
+        assert_eq!(list.back(&guard), Some(2));
+
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.back(&guard), Some(1));
+    }
+
+    #[test]
+    fn shared_collector() {
+        let collector = Arc::new(Collector::new());
+        let a = LinkedList::with_collector(collector.clone());
+        let b = LinkedList::with_collector(collector.clone());
+
+        let guard = collector.enter();
+        a.push_back_with(1, &guard);
+        a.push_front_with(0, &guard);
+        b.push_front_with(a.pop_back_with(&guard).unwrap(), &guard);
+        assert_eq!(a.pop_front_if_with(|&i| i > 0, &guard), None);
+        assert_eq!(a.pop_front_with(&guard), Some(0));
+        assert_eq!(b.back(&guard), Some(1));
+    }
+
+    #[test]
+    fn pop_front_if() {
+        let list = LinkedList::new();
+        assert_eq!(list.pop_front_if(|_| true), None);
+
+        list.push_back(1);
+        list.push_back(2);
+        assert_eq!(list.pop_front_if(|&x| x == 2), None);
+        assert_eq!(list.pop_front_if(|&x| x == 1), Some(1));
+        assert_eq!(list.pop_front_if(|&x| x == 1), None);
+        assert_eq!(list.pop_front(), Some(2));
+    }
+
+    #[test]
+    fn pop_front_if_multi() {
+        testing::pop_front_if_multi(&LinkedList::new());
+    }
+
+    #[test]
+    #[should_panic = "popped an element the same thread is still reading"]
+    #[cfg_attr(miri, ignore = "the panicking pop leaks its node")]
+    fn pop_front_if_reentrant() {
+        testing::pop_front_if_reentrant(&LinkedList::new());
+    }
+
+    #[test]
+    fn collection_traits() {
+        let mut list: LinkedList<i32> = (1..=3).collect();
+        list.extend([4, 5]);
+        assert_eq!(format!("{:?}", list), "[1, 2, 3, 4, 5]");
+        assert_eq!(format!("{:?}", LinkedList::<i32>::default()), "[]");
+
+        assert!(list.into_iter().rev().eq((1..=5).rev()));
+
+        let mut list = LinkedList::new();
+        list.extend(3..=5);
+        list.push_front(2);
+        list.push_front(1);
+        assert!(list.into_iter().eq(1..=5));
+    }
+
+    #[test]
+    fn send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<LinkedList<u8>>();
+        assert_send_sync::<LinkedList<std::sync::mpsc::Sender<u8>>>();
+    }
+
+    #[test]
+    fn ends_meet() {
+        let list = LinkedList::new();
+        list.push_front(1);
+        assert_eq!(list.pop_back(), Some(1));
+        assert_eq!(list.pop_front(), None);
+
+        list.push_back(2);
+        assert_eq!(list.pop_front(), Some(2));
+        assert_eq!(list.pop_back(), None);
+
+        list.push_back(3);
+        list.push_front(4);
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_back(), Some(4));
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn model() {
+        const SEEDS: u64 = if cfg!(miri) { 2 } else { 64 };
+        const OPS: u32 = if cfg!(miri) { 100 } else { 1000 };
+
+        for seed in 1..=SEEDS {
+            let mut rng = Rng(seed);
+            let list = LinkedList::new();
+            let mut model = VecDeque::new();
+
+            for i in 0..OPS {
+                match rng.next() % 4 {
+                    0 => {
+                        list.push_front(i);
+                        model.push_front(i);
+                    }
+                    1 => {
+                        list.push_back(i);
+                        model.push_back(i);
+                    }
+                    2 => assert_eq!(list.pop_front(), model.pop_front(), "seed {seed}"),
+                    _ => assert_eq!(list.pop_back(), model.pop_back(), "seed {seed}"),
+                }
+                assert_eq!(list.approximate_len(), model.len(), "seed {seed}");
+            }
+
+            while let Some(value) = model.pop_front() {
+                assert_eq!(list.pop_front(), Some(value), "seed {seed}");
+            }
+            assert_eq!(list.pop_back(), None);
+        }
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    enum Op {
+        PushFront(u32),
+        PushBack(u32),
+        PopFront(Option<u32>),
+        PopBack(Option<u32>),
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    struct Event {
+        op: Op,
+        call: u64,
+        ret: u64,
+    }
+
+    // Wing & Gong: pick any pending operation that could have taken effect
+    // first, meaning it was called before every other pending operation
+    // returned, replay it against a sequential deque and backtrack if the
+    // result it observed does not match.
+    fn linearizable(events: &[Event], done: &mut [bool], model: &mut VecDeque<u32>) -> bool {
+        let Some(first_ret) = (0..events.len())
+            .filter(|&i| !done[i])
+            .map(|i| events[i].ret)
+            .min()
+        else {
+            return true;
+        };
+
+        for i in 0..events.len() {
+            if done[i] || events[i].call > first_ret {
+                continue;
+            }
+
+            let applied = match events[i].op {
+                Op::PushFront(value) => {
+                    model.push_front(value);
+                    true
+                }
+                Op::PushBack(value) => {
+                    model.push_back(value);
+                    true
+                }
+                Op::PopFront(value) => {
+                    model.front().copied() == value && {
+                        model.pop_front();
+                        true
+                    }
+                }
+                Op::PopBack(value) => {
+                    model.back().copied() == value && {
+                        model.pop_back();
+                        true
+                    }
+                }
+            };
+
+            if !applied {
+                continue;
+            }
+
+            done[i] = true;
+            if linearizable(events, done, model) {
+                return true;
+            }
+            done[i] = false;
+
+            match events[i].op {
+                Op::PushFront(_) => drop(model.pop_front()),
+                Op::PushBack(_) => drop(model.pop_back()),
+                Op::PopFront(value) => {
+                    if let Some(value) = value {
+                        model.push_front(value);
+                    }
+                }
+                Op::PopBack(value) => model.extend(value),
+            }
+        }
+
+        false
+    }
+
+    #[test]
+    fn linearizability() {
+        const ROUNDS: u64 = if cfg!(miri) { 4 } else { 500 };
+        const THREADS: u32 = 4;
+        const OPS: u32 = 4;
+
+        for round in 0..ROUNDS {
+            let list = LinkedList::new();
+            let mut model = VecDeque::new();
+            for value in 100..102 {
+                list.push_back(value);
+                model.push_back(value);
+            }
+
+            let clock = AtomicU64::new(0);
+            let b = Barrier::new(THREADS as usize);
+            let events = thread::scope(|s| {
+                let handles: Vec<_> = (0..THREADS)
+                    .map(|t| {
+                        let (list, clock, b) = (&list, &clock, &b);
+                        s.spawn(move || {
+                            let mut rng = Rng(round * u64::from(THREADS) + u64::from(t) + 1);
+                            let mut events = Vec::new();
+                            b.wait();
+                            for i in 0..OPS {
+                                let value = t * OPS + i;
+                                let call = clock.fetch_add(1, Ordering::SeqCst);
+                                let op = match rng.next() % 4 {
+                                    0 => {
+                                        list.push_front(value);
+                                        Op::PushFront(value)
+                                    }
+                                    1 => {
+                                        list.push_back(value);
+                                        Op::PushBack(value)
+                                    }
+                                    2 => Op::PopFront(list.pop_front()),
+                                    _ => Op::PopBack(list.pop_back()),
+                                };
+                                let ret = clock.fetch_add(1, Ordering::SeqCst);
+                                events.push(Event { op, call, ret });
+                            }
+                            events
+                        })
+                    })
+                    .collect();
+
+                handles
+                    .into_iter()
+                    .flat_map(|h| h.join().unwrap())
+                    .collect::<Vec<_>>()
+            });
+
+            let mut done = vec![false; events.len()];
+            assert!(
+                linearizable(&events, &mut done, &mut model),
+                "round {round}: {events:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn push_pop_stress() {
+        const THREADS: u32 = 8;
+        const ITER: u32 = if cfg!(miri) { 20 } else { 2000 };
+
+        let list = LinkedList::new();
+        let popped = Mutex::new(Vec::new());
+        let remaining = AtomicUsize::new((THREADS * ITER) as usize);
+        let b = Barrier::new(THREADS as usize * 2);
+
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                let (list, b) = (&list, &b);
+                s.spawn(move || {
+                    b.wait();
+                    for i in 0..ITER {
+                        if (t + i) % 2 == 0 {
+                            list.push_front(t * ITER + i);
+                        } else {
+                            list.push_back(t * ITER + i);
+                        }
+                    }
+                });
+            }
+
+            for t in 0..THREADS {
+                let (list, b, popped, remaining) = (&list, &b, &popped, &remaining);
+                s.spawn(move || {
+                    let mut local = Vec::new();
+                    b.wait();
+                    while remaining.load(Ordering::Acquire) > 0 {
+                        let value = if (t as usize + local.len()).is_multiple_of(2) {
+                            list.pop_front()
+                        } else {
+                            list.pop_back()
+                        };
+                        if let Some(value) = value {
+                            remaining.fetch_sub(1, Ordering::AcqRel);
+                            local.push(value);
+                        }
+                    }
+                    popped.lock().unwrap().extend(local);
+                });
+            }
+        });
+
+        let mut popped = popped.into_inner().unwrap();
+        popped.sort_unstable();
+        assert_eq!(popped, (0..THREADS * ITER).collect::<Vec<_>>());
+        assert!(list.is_empty());
+        assert_eq!(list.pop_back(), None);
+    }
+}
+
+#[cfg(all(test, loom))]
+mod models {
+    use super::*;
+    use crate::reclaim::CheckedReclaimer;
+    use crate::testing::models::{race, Checked};
+
+    type CheckedList = LinkedList<u32, CheckedReclaimer>;
+
+    #[test]
+    fn push_both_ends() {
+        loom::model(|| {
+            let list = CheckedList::with_values(&[0]);
+            race(&list, |l| l.push_front(1), |l| l.push_back(2));
+            assert_eq!(list.drain(), [1, 0, 2]);
+        });
+    }
+
+    // the second push to an end finds the anchor unstable and has to finish
+    // linking the first one
+    #[test]
+    fn push_back_push_back() {
+        loom::model(|| {
+            let list = CheckedList::with_values(&[0]);
+            race(&list, |l| l.push_back(1), |l| l.push_back(2));
+            let popped = list.drain();
+            assert!(popped == [0, 1, 2] || popped == [0, 2, 1]);
+        });
+    }
+
+    #[test]
+    fn push_front_pop_back() {
+        loom::model(|| {
+            let list = CheckedList::with_values(&[]);
+            let (_, popped) = race(&list, |l| l.push_front(1), |l| l.pop_back());
+            match popped {
+                Some(1) => assert!(list.is_empty()),
+                None => assert_eq!(list.drain(), [1]),
+                _ => unreachable!(),
+            }
+        });
+    }
+
+    #[test]
+    fn pop_both_ends_last() {
+        loom::model(|| {
+            let list = CheckedList::with_values(&[1]);
+            let (front, back) = race(&list, |l| l.pop_front(), |l| l.pop_back());
+            assert!(matches!((front, back), (Some(1), None) | (None, Some(1))));
+            assert!(list.is_empty());
+        });
+    }
+
+    #[test]
+    fn pop_both_ends() {
+        loom::model(|| {
+            let list = CheckedList::with_values(&[1, 2]);
+            let (front, back) = race(&list, |l| l.pop_front(), |l| l.pop_back());
+            assert_eq!((front, back), (Some(1), Some(2)));
+            assert!(list.is_empty());
+        });
+    }
+
+    #[test]
+    fn push_back_pop_back() {
+        loom::model(|| {
+            let list = CheckedList::with_values(&[1]);
+            let (_, popped) = race(&list, |l| l.push_back(2), |l| l.pop_back());
+            match popped {
+                Some(1) => assert_eq!(list.drain(), [2]),
+                Some(2) => assert_eq!(list.drain(), [1]),
+                _ => unreachable!(),
+            }
+        });
+    }
 }
@@ -0,0 +1,60 @@
+//! Opt-in instrumentation of the lock-free fast paths.
+//!
+//! Enabled with the `instrument` feature. Without it the event sites compile
+//! to nothing.
+
+use std::sync::OnceLock;
+
+/// Something that happened inside a container operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Event {
+    /// A CAS on a shared pointer failed and the operation had to retry.
+    CasLost,
+    /// The thread moved a lagging `head` forward on behalf of another operation.
+    HelpedAdvanceHead,
+    /// The thread moved a lagging `tail` forward on behalf of another operation.
+    HelpedAdvanceTail,
+    /// The thread unlinked a node that another thread had claimed.
+    HelpedUnlink,
+    /// The thread helped finish linking a node pushed by another thread.
+    HelpedStabilize,
+}
+
+/// Receives [`Event`]s, either from every container in the process through
+/// [`set_hook`] or from a single one through
+/// [`Builder::hook`](crate::builder::Builder::hook).
+///
+/// Hooks are called on the hot path, from whichever thread hit the event,
+/// so they should be cheap and must not block.
+pub trait Hook: Send + Sync {
+    fn event(&self, event: Event);
+}
+
+impl<F> Hook for F
+where
+    F: Fn(Event) + Send + Sync,
+{
+    fn event(&self, event: Event) {
+        self(event)
+    }
+}
+
+static HOOK: OnceLock<Box<dyn Hook>> = OnceLock::new();
+
+/// Installs the process-wide hook.
+///
+/// The hook can only be set once, later calls hand the rejected hook back.
+pub fn set_hook(hook: Box<dyn Hook>) -> Result<(), Box<dyn Hook>> {
+    HOOK.set(hook)
+}
+
+#[inline]
+pub(crate) fn emit(local: Option<&dyn Hook>, event: Event) {
+    if let Some(hook) = local {
+        hook.event(event);
+    }
+    if let Some(hook) = HOOK.get() {
+        hook.event(event);
+    }
+}
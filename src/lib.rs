@@ -1,41 +1,219 @@
-use seize::{reclaim, AtomicPtr, Collector};
-use std::sync::atomic::Ordering;
-use std::{mem::ManuallyDrop, ptr};
+use std::sync::Arc;
+use std::{fmt, ptr, vec};
 
+// Reports an instrumentation event, compiles to nothing unless the
+// `instrument` feature is enabled.
+macro_rules! instrument {
+    ($container:ident, $event:ident) => {
+        #[cfg(feature = "instrument")]
+        $crate::instrument::emit(
+            $container.hook.as_deref(),
+            $crate::instrument::Event::$event,
+        );
+    };
+}
+
+pub mod builder;
+pub mod channel;
 pub mod doubly;
+#[cfg(feature = "instrument")]
+pub mod instrument;
+mod parking;
 pub mod queue;
+pub mod reclaim;
+mod slot;
+mod sync;
+#[cfg(test)]
+mod testing;
+
+use builder::{Build, Builder};
+#[cfg(feature = "instrument")]
+use instrument::Hook;
+use reclaim::{Collector, DefaultReclaimer, Reclaimer, SeizeReclaimer};
+use slot::Slot;
+use sync::{AtomicPtr, AtomicU8, AtomicUsize, Ordering};
 
-pub struct LinkedList<T> {
-    head: AtomicPtr<Node<T>>,
-    collector: Collector,
+/// The containers hand values from one thread to another, so they must not
+/// be `Send` unless `T: Send`. Iterators and peeks also look at the values
+/// from any thread sharing the container, so they must not be `Sync` unless
+/// `T: Send + Sync`:
+///
+/// ```compile_fail
+/// use std::{rc::Rc, thread};
+///
+/// let queue = wal::queue::Queue::new();
+/// queue.push_back(Rc::new(1_u8));
+/// thread::scope(|s| {
+///     s.spawn(|| drop(queue.pop_front()));
+/// });
+/// ```
+///
+/// ```compile_fail
+/// use std::{rc::Rc, thread};
+///
+/// let queue = wal::queue::Queue::<Rc<u8>>::new();
+/// thread::spawn(move || drop(queue));
+/// ```
+///
+/// ```compile_fail
+/// use std::{rc::Rc, thread};
+///
+/// let list = wal::LinkedList::new();
+/// list.push_back(Rc::new(1_u8));
+/// thread::scope(|s| {
+///     s.spawn(|| drop(list.pop_front()));
+/// });
+/// ```
+///
+/// ```compile_fail
+/// use std::{cell::Cell, rc::Rc, thread};
+///
+/// let list = wal::doubly::LinkedList::new();
+/// list.push_back(Rc::new(Cell::new(1_u8)));
+/// thread::scope(|s| {
+///     s.spawn(|| drop(list.pop_back()));
+/// });
+/// ```
+///
+/// ```compile_fail
+/// use std::{cell::RefCell, thread};
+///
+/// let queue = wal::queue::Queue::new();
+/// queue.push_back(RefCell::new(1_u8));
+/// thread::scope(|s| {
+///     s.spawn(|| queue.iter(&queue.guard()).next_with(|c| *c.borrow()));
+/// });
+/// ```
+#[cfg(doctest)]
+pub struct CompileFailTests;
+
+pub struct LinkedList<T, R: Reclaimer = DefaultReclaimer> {
+    head: Link<T, R>,
+    // A recently pushed node to start looking for the end from, or null.
+    // Never points at freed memory, see `Node::handoff`.
+    tail: Link<T, R>,
+    // counts a push before its node is linked and a pop after it claimed one
+    len: AtomicUsize,
+    reclaimer: R,
+    #[cfg(feature = "instrument")]
+    hook: Option<Arc<dyn Hook>>,
 }
 
+unsafe impl<T: Send, R: Reclaimer> Send for LinkedList<T, R> {}
+unsafe impl<T: Send + Sync, R: Reclaimer> Sync for LinkedList<T, R> {}
+
+type Link<T, R> = AtomicPtr<<R as Reclaimer>::Linked<Node<T, R>>>;
+type NodePtr<T, R> = *mut <R as Reclaimer>::Linked<Node<T, R>>;
+
 #[derive(Debug)]
-pub struct Node<T> {
-    inner: ManuallyDrop<T>,
-    next: AtomicPtr<Node<T>>,
+pub struct Node<T, R: Reclaimer> {
+    inner: Slot<T>,
+    next: Link<T, R>,
+    // A node pushed to the back is stored as the `tail` hint after it is
+    // linked, possibly after it was already popped. The pusher and the
+    // unlinker each set their bit when done, and whoever comes second clears
+    // the hint and retires the node.
+    handoff: AtomicU8,
+}
+
+const PUSHED: u8 = 1;
+const UNLINKED: u8 = 2;
+
+impl<T, R: Reclaimer> Node<T, R> {
+    fn new(t: T, handoff: u8) -> Self {
+        Self {
+            inner: Slot::new(t),
+            next: AtomicPtr::new(ptr::null_mut()),
+            handoff: AtomicU8::new(handoff),
+        }
+    }
+}
+
+// The low bit of `next` marks a node as claimed by a pop. Whoever sets the
+// mark owns the value, whoever swings the predecessor's link past it retires it.
+const MARK: usize = 1;
+
+#[inline]
+fn is_marked<T>(ptr: *mut T) -> bool {
+    ptr.addr() & MARK != 0
+}
+
+#[inline]
+fn marked<T>(ptr: *mut T) -> *mut T {
+    ptr.map_addr(|addr| addr | MARK)
+}
+
+#[inline]
+fn unmarked<T>(ptr: *mut T) -> *mut T {
+    ptr.map_addr(|addr| addr & !MARK)
+}
+
+#[inline]
+fn assert_guard<R: Reclaimer>(guard: &R::Guard<'_>, reclaimer: &R) {
+    assert!(
+        reclaimer.owns(guard),
+        "guard belongs to a different reclaimer"
+    );
 }
 
 impl<T> LinkedList<T> {
     pub fn new() -> Self {
+        Self::with_reclaimer(DefaultReclaimer::new())
+    }
+
+    /// Creates a list that retires its nodes into `collector`, which may be
+    /// shared with other containers.
+    pub fn with_collector(collector: Arc<Collector>) -> Self {
+        Self::with_reclaimer(SeizeReclaimer::shared(collector))
+    }
+}
+
+impl<T> Build for LinkedList<T> {
+    fn from_builder(builder: Builder) -> Self {
+        builder.unbounded();
+        #[allow(unused_mut)]
+        let mut list = Self::with_reclaimer(builder.reclaimer());
+        list.reclaimer
+            .preallocate::<Node<T, SeizeReclaimer>>(builder.preallocate);
+        #[cfg(feature = "instrument")]
+        {
+            list.hook = builder.hook;
+        }
+        list
+    }
+}
+
+impl<T, R: Reclaimer> LinkedList<T, R> {
+    pub fn with_reclaimer(reclaimer: R) -> Self {
         Self {
             head: AtomicPtr::new(ptr::null_mut()),
-            collector: Collector::new(),
+            tail: AtomicPtr::new(ptr::null_mut()),
+            len: AtomicUsize::new(0),
+            reclaimer,
+            #[cfg(feature = "instrument")]
+            hook: None,
         }
     }
 
     #[inline]
     pub fn push_front(&self, t: T) {
-        let new = self.collector.link_boxed(Node {
-            inner: ManuallyDrop::new(t),
-            next: AtomicPtr::new(ptr::null_mut()),
-        });
+        self.push_front_with(t, &self.reclaimer.enter());
+    }
 
-        let guard = self.collector.enter();
+    /// Like [`LinkedList::push_front`], under a guard the caller already holds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this list's reclaimer.
+    #[inline]
+    pub fn push_front_with(&self, t: T, guard: &R::Guard<'_>) {
+        assert_guard(guard, &self.reclaimer);
+        let new = self.reclaimer.link_boxed(Node::new(t, PUSHED));
+        self.len.fetch_add(1, Ordering::Release);
 
         loop {
-            let head = guard.protect(&self.head, Ordering::Acquire);
-            unsafe { (*new).next.store(head, Ordering::Release) }
+            let head = R::protect(guard, &self.head, Ordering::Acquire);
+            unsafe { &*new }.next.store(head, Ordering::Release);
 
             if self
                 .head
@@ -44,100 +222,589 @@ impl<T> LinkedList<T> {
             {
                 break;
             }
+            instrument!(self, CasLost);
         }
     }
 
     #[inline]
     pub fn push_back(&self, t: T) {
-        let new = self.collector.link_boxed(Node {
-            inner: ManuallyDrop::new(t),
-            next: AtomicPtr::new(ptr::null_mut()),
-        });
+        self.push_back_with(t, &self.reclaimer.enter());
+    }
 
-        let guard = self.collector.enter();
+    /// Like [`LinkedList::push_back`], under a guard the caller already holds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this list's reclaimer.
+    #[inline]
+    pub fn push_back_with(&self, t: T, guard: &R::Guard<'_>) {
+        assert_guard(guard, &self.reclaimer);
+        let new = self.reclaimer.link_boxed(Node::new(t, 0));
+        self.len.fetch_add(1, Ordering::Release);
 
-        let mut current = guard.protect(&self.head, Ordering::Acquire);
-        if current.is_null() {
-            loop {
-                match self
-                    .head
-                    .compare_exchange(current, new, Ordering::Acquire, Ordering::Relaxed)
-                {
-                    Ok(_) => {
-                        break;
-                    }
-                    Err(curr) => {
-                        current = curr;
-                        continue;
+        loop {
+            let onto = self.find_end(guard);
+            if onto
+                .compare_exchange(ptr::null_mut(), new, Ordering::Release, Ordering::Relaxed)
+                .is_ok()
+            {
+                break;
+            }
+            instrument!(self, CasLost);
+        }
+
+        self.tail.store(new, Ordering::Release);
+        if unsafe { &*new }.handoff.fetch_or(PUSHED, Ordering::AcqRel) & UNLINKED != 0 {
+            unsafe { self.retire_hinted(new) };
+        }
+    }
+
+    /// Returns the null link at the end of the list, starting from the
+    /// `tail` hint if it is still usable and from `head` otherwise.
+    #[inline]
+    fn find_end<'a>(&'a self, guard: &R::Guard<'_>) -> &'a Link<T, R> {
+        let mut current = R::protect(guard, &self.tail, Ordering::Acquire);
+
+        // a marked node may already be unlinked, and appending behind it
+        // would lose the new node, so only walk over live ones
+        while !current.is_null() {
+            let node = unsafe { &*current };
+            let next = R::protect(guard, &node.next, Ordering::Acquire);
+            if is_marked(next) {
+                break;
+            }
+            if next.is_null() {
+                return &node.next;
+            }
+            current = next;
+        }
+
+        match self.find_last(guard) {
+            (link, last) if last.is_null() => link,
+            (_, last) => &unsafe { &*last }.next,
+        }
+    }
+
+    /// Removes the front element.
+    ///
+    /// While another thread looks at the element through an iterator or a
+    /// peek, this spins until it is done.
+    #[inline]
+    pub fn pop_front(&self) -> Option<T> {
+        self.pop_front_with(&self.reclaimer.enter())
+    }
+
+    /// Removes the front element only if `predicate` returns `true` for it.
+    ///
+    /// The predicate may be called more than once if the front changes
+    /// concurrently, the element it last accepted is the one returned. Pops
+    /// of the element it is looking at stall on other threads until it
+    /// returns, and it must not pop from this list itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the predicate pops the element it is looking at, which would
+    /// otherwise wait for the predicate forever. That element is leaked.
+    pub fn pop_front_if(&self, predicate: impl FnMut(&T) -> bool) -> Option<T> {
+        self.pop_front_if_with(predicate, &self.reclaimer.enter())
+    }
+
+    /// Like [`LinkedList::pop_front`], under a guard the caller already holds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this list's reclaimer.
+    #[inline]
+    pub fn pop_front_with(&self, guard: &R::Guard<'_>) -> Option<T> {
+        self.pop_front_matching(None, guard)
+    }
+
+    /// Like [`LinkedList::pop_front_if`], under a guard the caller already
+    /// holds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this list's reclaimer.
+    pub fn pop_front_if_with(
+        &self,
+        mut predicate: impl FnMut(&T) -> bool,
+        guard: &R::Guard<'_>,
+    ) -> Option<T> {
+        self.pop_front_matching(Some(&mut predicate), guard)
+    }
+
+    // Without a predicate any element matches, and the value is not read
+    // before the node is claimed.
+    fn pop_front_matching(
+        &self,
+        mut predicate: Option<&mut dyn FnMut(&T) -> bool>,
+        guard: &R::Guard<'_>,
+    ) -> Option<T> {
+        assert_guard(guard, &self.reclaimer);
+
+        'retry: loop {
+            let link = &self.head;
+            let mut current = R::protect(guard, link, Ordering::Acquire);
+
+            while !current.is_null() {
+                let next = R::protect(guard, &unsafe { &*current }.next, Ordering::Acquire);
+
+                if is_marked(next) {
+                    // someone else claimed this node, unlink it before looking further
+                    if !unsafe { self.unlink(link, current, next) } {
+                        continue 'retry;
                     }
+                    instrument!(self, HelpedAdvanceHead);
+                    current = unmarked(next);
+                    continue;
                 }
-            }
-        } else {
-            let mut current = guard.protect(&self.head, Ordering::Acquire);
-            loop {
-                let next = unsafe { &*current }.next.load(Ordering::Acquire);
-                if next.is_null() {
-                    match unsafe { &*current }.next.compare_exchange(
-                        next,
-                        new,
-                        Ordering::Acquire,
-                        Ordering::Relaxed,
-                    ) {
-                        Ok(_) => {
-                            break;
-                        }
-                        Err(actual_next) => {
-                            // current has next element linked
-                            current = actual_next;
-                            continue;
-                        }
+
+                // a pop that claimed the node would wait for the predicate
+                // before taking the value
+                if let Some(predicate) = predicate.as_mut() {
+                    match unsafe { &*current }.inner.read(|t| predicate(t)) {
+                        Some(true) => {}
+                        Some(false) => return None,
+                        None => continue 'retry,
                     }
-                } else {
-                    // check if next->next is not null
-                    let n = unsafe { &*next }.next.load(Ordering::Acquire);
-                    if n.is_null() {
-                        current = next;
-                    } else {
-                        current = n;
+                }
+
+                if unsafe { &*current }
+                    .next
+                    .compare_exchange(next, marked(next), Ordering::AcqRel, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    self.len.fetch_sub(1, Ordering::Release);
+                    unsafe {
+                        let data = (&*current).inner.take();
+                        self.unlink(link, current, marked(next));
+                        return Some(data);
                     }
                 }
+                instrument!(self, CasLost);
             }
+
+            return None;
         }
     }
 
+    /// Removes the back element.
+    ///
+    /// While another thread looks at the element through an iterator or a
+    /// peek, this spins until it is done.
     #[inline]
-    pub fn pop_front(&self) -> Option<T> {
-        let guard = self.collector.enter();
+    pub fn pop_back(&self) -> Option<T> {
+        self.pop_back_with(&self.reclaimer.enter())
+    }
 
-        loop {
-            let head = guard.protect(&self.head, Ordering::Acquire);
+    /// Like [`LinkedList::pop_back`], under a guard the caller already holds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this list's reclaimer.
+    #[inline]
+    pub fn pop_back_with(&self, guard: &R::Guard<'_>) -> Option<T> {
+        assert_guard(guard, &self.reclaimer);
 
-            if head.is_null() {
+        loop {
+            let (link, last) = self.find_last(guard);
+            if last.is_null() {
                 return None;
             }
 
-            let next = unsafe { (*head).next.load(Ordering::Acquire) };
-
-            if self
-                .head
-                .compare_exchange(head, next, Ordering::AcqRel, Ordering::Relaxed)
+            if unsafe { &*last }
+                .next
+                .compare_exchange(
+                    ptr::null_mut(),
+                    marked(ptr::null_mut()),
+                    Ordering::AcqRel,
+                    Ordering::Relaxed,
+                )
                 .is_ok()
             {
+                self.len.fetch_sub(1, Ordering::Release);
                 unsafe {
-                    let data = ptr::read(&(*head).inner);
-                    self.collector.retire(head, reclaim::boxed::<Node<T>>);
-                    return Some(ManuallyDrop::into_inner(data));
+                    let data = (&*last).inner.take();
+                    self.unlink(link, last, marked(ptr::null_mut()));
+                    return Some(data);
                 }
             }
+            instrument!(self, CasLost);
         }
     }
 
-    pub fn pop_back(&self) -> Option<T> {
-        todo!()
+    /// Returns the elements front to back, skipping nodes that were claimed
+    /// by a pop but are still linked, after checking that the `tail` hint is
+    /// null or one of the nodes. For looking at the structure of the list in
+    /// tests, so only available in debug builds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the hint points outside the list.
+    #[cfg(any(test, debug_assertions))]
+    pub fn debug_nodes(&mut self) -> Vec<&T> {
+        let tail = self.tail.load(Ordering::Relaxed);
+        let mut found_tail = tail.is_null();
+
+        let mut nodes = Vec::new();
+        let mut current = self.head.load(Ordering::Relaxed);
+        while !current.is_null() {
+            let node = unsafe { &*current };
+            let next = node.next.load(Ordering::Relaxed);
+            if !is_marked(next) {
+                nodes.push(unsafe { node.inner.get() });
+            }
+            found_tail |= current == tail;
+            current = unmarked(next);
+        }
+        assert!(found_tail, "the `tail` hint is not in the list");
+        nodes
+    }
+
+    /// Detaches every element with a single swap of `head`, returning them
+    /// front to back. Use `.rev()` on the result to get them in the order
+    /// they were pushed with [`LinkedList::push_front`].
+    ///
+    /// A concurrent [`LinkedList::push_back`] that already found its place
+    /// in the detached nodes still ends up in the result.
+    pub fn take_all(&self) -> TakeAll<T> {
+        let guard = self.reclaimer.enter();
+        let mut current = self.head.swap(ptr::null_mut(), Ordering::AcqRel);
+        let mut values = Vec::new();
+
+        // Marking each node closes its `next` to pushes and unlinks, so every
+        // node we reach after marking its predecessor is ours to retire.
+        // Nodes already marked by a pop still have their value taken by it.
+        while !current.is_null() {
+            let node = unsafe { &*current };
+            let mut next = R::protect(&guard, &node.next, Ordering::Acquire);
+            while !is_marked(next) {
+                match node.next.compare_exchange(
+                    next,
+                    marked(next),
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                ) {
+                    Ok(_) => {
+                        values.push(unsafe { node.inner.take() });
+                        break;
+                    }
+                    Err(actual) => next = actual,
+                }
+            }
+
+            unsafe { self.retire(current) };
+            current = unmarked(R::protect(&guard, &node.next, Ordering::Acquire));
+        }
+
+        self.len.fetch_sub(values.len(), Ordering::Release);
+        TakeAll(values.into_iter())
+    }
+
+    /// Returns the number of elements, counting a push from before its node
+    /// is linked and a pop until after it claimed its node.
+    ///
+    /// The count is at least the length at the moment it is read, and at
+    /// most that plus the number of pushes and pops in progress. It is exact
+    /// while no operation is.
+    pub fn approximate_len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// Returns whether the list has no elements, as of reading `head`.
+    ///
+    /// Claimed nodes still at the front are unlinked first, as a pop would.
+    pub fn is_empty(&self) -> bool {
+        let guard = self.reclaimer.enter();
+
+        loop {
+            let head = R::protect(&guard, &self.head, Ordering::Acquire);
+            if head.is_null() {
+                return true;
+            }
+
+            // a node is only unlinked after it was marked, so if it is not
+            // marked now it was still live when `head` was read
+            let next = R::protect(&guard, &unsafe { &*head }.next, Ordering::Acquire);
+            if !is_marked(next) {
+                return false;
+            }
+            unsafe { self.unlink(&self.head, head, next) };
+        }
+    }
+
+    /// Enters the list's reclaimer, for use with [`LinkedList::iter`] and the
+    /// `*_with` methods.
+    pub fn guard(&self) -> R::Guard<'_> {
+        self.reclaimer.enter()
+    }
+
+    /// Iterates over clones of the elements from front to back.
+    ///
+    /// Nodes stay allocated for as long as `guard` is held, elements pushed or
+    /// popped concurrently may or may not be observed. The elements are
+    /// cloned because a pop may move them out at any time, use
+    /// [`Iter::next_with`] to look at them in place, which does not need
+    /// `T: Clone`. A pop of an element that is being cloned or looked at
+    /// spins until that is done.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this list's reclaimer.
+    pub fn iter<'g>(&'g self, guard: &'g R::Guard<'_>) -> Iter<'g, T, R> {
+        assert_guard(guard, &self.reclaimer);
+
+        Iter {
+            current: R::protect(guard, &self.head, Ordering::Acquire),
+            guard: R::reborrow(guard),
+        }
+    }
+
+    /// Returns a clone of the element at the front without removing it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this list's reclaimer.
+    pub fn front(&self, guard: &R::Guard<'_>) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.front_with(guard, T::clone)
+    }
+
+    /// Calls `f` with the element at the front without removing it, and
+    /// returns what it returns. Unlike [`LinkedList::front`] this does not need
+    /// `T: Clone`.
+    ///
+    /// A pop of the element on another thread waits for `f` to return, and
+    /// `f` must not pop from the list itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this list's reclaimer, or if `f`
+    /// pops the element it is given.
+    pub fn front_with<U>(&self, guard: &R::Guard<'_>, f: impl FnOnce(&T) -> U) -> Option<U> {
+        self.iter(guard).next_with(f)
+    }
+
+    /// Walks to the last live node, unlinking claimed nodes on the way.
+    ///
+    /// Returns the node together with the link pointing at it, or `head` and
+    /// a null pointer if the list is empty.
+    #[inline]
+    fn find_last<'a>(&'a self, guard: &R::Guard<'_>) -> (&'a Link<T, R>, NodePtr<T, R>) {
+        'retry: loop {
+            let (mut prev_link, mut prev) = (&self.head, ptr::null_mut());
+            let mut link = &self.head;
+            let mut current = R::protect(guard, link, Ordering::Acquire);
+
+            while !current.is_null() {
+                let node = unsafe { &*current };
+                let next = R::protect(guard, &node.next, Ordering::Acquire);
+
+                if is_marked(next) {
+                    if !unsafe { self.unlink(link, current, next) } {
+                        continue 'retry;
+                    }
+                    instrument!(self, HelpedUnlink);
+                    current = unmarked(next);
+                    continue;
+                }
+
+                if next.is_null() {
+                    return (link, current);
+                }
+
+                (prev_link, prev) = (link, current);
+                link = &node.next;
+                current = next;
+            }
+
+            return (prev_link, prev);
+        }
+    }
+
+    /// Swings `link` past a claimed `node`, retiring it if this thread won.
+    #[inline]
+    unsafe fn unlink(&self, link: &Link<T, R>, node: NodePtr<T, R>, next: NodePtr<T, R>) -> bool {
+        if link
+            .compare_exchange(node, unmarked(next), Ordering::AcqRel, Ordering::Relaxed)
+            .is_ok()
+        {
+            self.retire(node);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Retires a node that was just unlinked, unless its pusher may still
+    /// store it as the `tail` hint.
+    #[inline]
+    unsafe fn retire(&self, node: NodePtr<T, R>) {
+        if (&*node).handoff.fetch_or(UNLINKED, Ordering::AcqRel) & PUSHED != 0 {
+            self.retire_hinted(node);
+        }
+    }
+
+    #[inline]
+    unsafe fn retire_hinted(&self, node: NodePtr<T, R>) {
+        let _ =
+            self.tail
+                .compare_exchange(node, ptr::null_mut(), Ordering::AcqRel, Ordering::Relaxed);
+        self.reclaimer.retire(node);
+    }
+}
+
+impl<T> Default for LinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<T> for LinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T, R: Reclaimer> Extend<T> for LinkedList<T, R> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        // with `&mut self` nothing else can touch the list, so find the end
+        // once and link the new nodes one after another
+        let guard = self.reclaimer.enter();
+        let mut link = self.find_end(&guard);
+
+        for t in iter {
+            let new = self.reclaimer.link_boxed(Node::new(t, PUSHED));
+            link.store(new, Ordering::Release);
+            link = &unsafe { &*new }.next;
+            self.tail.store(new, Ordering::Release);
+            self.len.fetch_add(1, Ordering::Release);
+        }
+    }
+}
+
+impl<T, R: Reclaimer> IntoIterator for LinkedList<T, R> {
+    type Item = T;
+    type IntoIter = IntoIter<T, R>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+/// An owning iterator that pops elements from the front.
+pub struct IntoIter<T, R: Reclaimer = DefaultReclaimer>(LinkedList<T, R>);
+
+impl<T, R: Reclaimer> Iterator for IntoIter<T, R> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_front()
+    }
+}
+
+impl<T, R: Reclaimer> DoubleEndedIterator for IntoIter<T, R> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_back()
+    }
+}
+
+impl<T: fmt::Debug, R: Reclaimer> fmt::Debug for LinkedList<T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let guard = self.guard();
+        let (mut iter, mut list) = (self.iter(&guard), f.debug_list());
+        // formatted in place, a pop of an element waits for it to be done
+        while iter
+            .next_with(|t| {
+                list.entry(t);
+            })
+            .is_some()
+        {}
+        list.finish()
+    }
+}
+
+/// The elements detached by [`LinkedList::take_all`], front to back.
+pub struct TakeAll<T>(vec::IntoIter<T>);
+
+impl<T> Iterator for TakeAll<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for TakeAll<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+impl<T> ExactSizeIterator for TakeAll<T> {}
+
+/// The iterator returned by [`LinkedList::iter`], which only implements
+/// [`Iterator`] for `T: Clone`.
+pub struct Iter<'g, T, R: Reclaimer + 'g = DefaultReclaimer> {
+    current: NodePtr<T, R>,
+    guard: &'g R::Guard<'g>,
+}
+
+impl<'g, T: 'g, R: Reclaimer> Iter<'g, T, R> {
+    fn next_slot(&mut self) -> Option<&'g Slot<T>> {
+        while !self.current.is_null() {
+            let node = unsafe { &*self.current };
+            let next = R::protect(self.guard, &node.next, Ordering::Acquire);
+            self.current = unmarked(next);
+
+            // claimed nodes are skipped, their value belongs to a pop
+            if !is_marked(next) {
+                return Some(&node.inner);
+            }
+        }
+
+        None
+    }
+
+    /// Calls `f` with the next element in place and returns what it returns,
+    /// which also works for elements that are not `Clone`.
+    ///
+    /// A pop of the element on another thread waits for `f` to return, and
+    /// `f` must not pop from the list itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` pops the element it is given.
+    pub fn next_with<U>(&mut self, f: impl FnOnce(&T) -> U) -> Option<U> {
+        let mut f = Some(f);
+        // a pop may still claim a node after it was reached
+        while let Some(slot) = self.next_slot() {
+            // `read` only calls `f` if the element is still there
+            if let Some(u) = slot.read(|t| f.take().unwrap()(t)) {
+                return Some(u);
+            }
+        }
+        None
+    }
+}
+
+impl<'g, T: Clone + 'g, R: Reclaimer> Iterator for Iter<'g, T, R> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with(T::clone)
     }
 }
 
-impl<T> Drop for LinkedList<T> {
+impl<T, R: Reclaimer> Drop for LinkedList<T, R> {
     fn drop(&mut self) {
         while self.pop_front().is_some() {}
     }
@@ -145,7 +812,14 @@ impl<T> Drop for LinkedList<T> {
 
 #[cfg(test)]
 mod tests {
+    use std::{
+        collections::{HashSet, VecDeque},
+        sync::{atomic::AtomicUsize, Barrier, Mutex},
+        thread,
+    };
+
     use super::*;
+    use crate::testing::{self, Rng};
 
     #[test]
     fn push_front() {
@@ -160,12 +834,615 @@ mod tests {
 
     #[test]
     fn push_back() {
-        let list = LinkedList::new();
+        let mut list = LinkedList::new();
         list.push_back(1);
         list.push_back(2);
         list.push_back(3);
+        assert_eq!(list.debug_nodes(), [&1, &2, &3]);
         assert_eq!(list.pop_front(), Some(1));
         assert_eq!(list.pop_front(), Some(2));
         assert_eq!(list.pop_front(), Some(3));
     }
+
+    #[test]
+    fn pop_back() {
+        let list = LinkedList::new();
+        assert_eq!(list.pop_back(), None);
+        list.push_back(1);
+        list.push_back(2);
+        list.push_front(0);
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.pop_back(), Some(1));
+        assert_eq!(list.pop_back(), Some(0));
+        assert_eq!(list.pop_back(), None);
+        assert_eq!(list.pop_front(), None);
+    }
+
+    #[test]
+    fn pop_both_ends() {
+        let list = LinkedList::new();
+        for i in 0..4 {
+            list.push_back(i);
+        }
+        assert_eq!(list.pop_front(), Some(0));
+        assert_eq!(list.pop_back(), Some(3));
+        list.push_back(4);
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_back(), Some(4));
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.pop_front(), None);
+    }
+
+    const THREADS: usize = 4;
+    const ITER: usize = if cfg!(miri) { 20 } else { 1000 };
+
+    #[test]
+    fn len() {
+        let list = LinkedList::new();
+        assert!(list.is_empty());
+        list.push_back(1);
+        list.push_back(2);
+        assert_eq!(list.approximate_len(), 2);
+        assert!(!list.is_empty());
+
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.approximate_len(), 1);
+        assert_eq!(list.pop_front(), Some(2));
+        assert_eq!(list.approximate_len(), 0);
+        assert!(list.is_empty());
+
+        list.push_front(1);
+        list.push_back(2);
+        assert_eq!(list.take_all().count(), 2);
+        assert_eq!(list.approximate_len(), 0);
+
+        let mut list: LinkedList<_> = (1..=3).collect();
+        assert_eq!(list.approximate_len(), 3);
+        list.extend([4, 5]);
+        assert_eq!(list.approximate_len(), 5);
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_back(), Some(5));
+        assert_eq!(list.approximate_len(), 3);
+        assert_eq!(list.take_all().count(), 3);
+        assert_eq!(list.approximate_len(), 0);
+    }
+
+    #[test]
+    fn len_bounds() {
+        testing::len_bounds(&LinkedList::new());
+    }
+
+    #[test]
+    fn push_pop_multi() {
+        let list = LinkedList::new();
+        let popped = Mutex::new(Vec::new());
+        let remaining = AtomicUsize::new(THREADS * ITER);
+        let b = Barrier::new(THREADS * 2);
+
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                let (list, b) = (&list, &b);
+                s.spawn(move || {
+                    b.wait();
+                    for i in 0..ITER {
+                        if i % 2 == 0 {
+                            list.push_front(t * ITER + i);
+                        } else {
+                            list.push_back(t * ITER + i);
+                        }
+                    }
+                });
+            }
+
+            for t in 0..THREADS {
+                let (list, b, popped, remaining) = (&list, &b, &popped, &remaining);
+                s.spawn(move || {
+                    let mut local = Vec::new();
+                    b.wait();
+                    while remaining.load(Ordering::Acquire) > 0 {
+                        let value = if (t + local.len()) % 2 == 0 {
+                            list.pop_front()
+                        } else {
+                            list.pop_back()
+                        };
+                        if let Some(value) = value {
+                            remaining.fetch_sub(1, Ordering::AcqRel);
+                            local.push(value);
+                        }
+                    }
+                    popped.lock().unwrap().extend(local);
+                });
+            }
+        });
+
+        let popped = popped.into_inner().unwrap();
+        assert_eq!(popped.len(), THREADS * ITER);
+        let unique: HashSet<_> = popped.into_iter().collect();
+        assert_eq!(unique, (0..THREADS * ITER).collect());
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+    }
+
+    #[test]
+    fn pop_back_preserves_order() {
+        let list = LinkedList::new();
+        let b = Barrier::new(2);
+        thread::scope(|s| {
+            s.spawn(|| {
+                b.wait();
+                for i in 0..ITER {
+                    list.push_back(i);
+                }
+            });
+
+            s.spawn(|| {
+                b.wait();
+                let mut last = None;
+                let mut seen = 0;
+                while seen < ITER {
+                    if let Some(value) = list.pop_front() {
+                        // a single producer appending at the back means the
+                        // front must only ever move forward
+                        assert!(last < Some(value));
+                        last = Some(value);
+                        seen += 1;
+                    }
+                }
+            });
+        });
+        assert_eq!(list.pop_back(), None);
+    }
+
+    #[test]
+    fn iter() {
+        let list = LinkedList::new();
+        list.push_back(2);
+        list.push_back(3);
+        list.push_front(1);
+
+        let guard = list.guard();
+        assert!(list.iter(&guard).eq([1, 2, 3]));
+
+        assert_eq!(list.pop_back(), Some(3));
+        assert!(list.iter(&guard).eq([1, 2]));
+    }
+
+    #[test]
+    fn iter_next_with() {
+        // not `Clone`, so only reachable in place
+        struct Job(Box<u32>);
+
+        let list = LinkedList::new();
+        let deque = doubly::LinkedList::new();
+        let queue = queue::Queue::new();
+        for i in 1..=3 {
+            list.push_back(Job(Box::new(i)));
+            deque.push_back(Job(Box::new(i)));
+            queue.push_back(Job(Box::new(i)));
+        }
+
+        let guard = list.guard();
+        let mut iter = list.iter(&guard);
+        assert_eq!(iter.next_with(|job| *job.0), Some(1));
+        assert_eq!(list.pop_front().map(|job| *job.0), Some(1));
+        assert_eq!(list.pop_back().map(|job| *job.0), Some(3));
+        assert_eq!(iter.next_with(|job| *job.0), Some(2));
+        assert_eq!(iter.next_with(|job| *job.0), None);
+
+        let guard = deque.guard();
+        let mut iter = deque.iter(&guard);
+        assert_eq!(deque.pop_front().map(|job| *job.0), Some(1));
+        assert_eq!(iter.next_with(|job| *job.0), Some(2));
+        assert_eq!(iter.next_with(|job| *job.0), Some(3));
+        assert_eq!(iter.next_with(|job| *job.0), None);
+
+        let guard = queue.guard();
+        let mut iter = queue.iter(&guard);
+        assert_eq!(queue.pop_front().map(|job| *job.0), Some(1));
+        assert_eq!(iter.next_with(|job| *job.0), Some(2));
+        assert_eq!(iter.next_with(|job| *job.0), Some(3));
+        assert_eq!(iter.next_with(|job| *job.0), None);
+    }
+
+    #[test]
+    fn front_with() {
+        // not `Clone`, like a job that is only peeked at for its deadline
+        struct Job {
+            deadline: u32,
+            _payload: Box<[u8]>,
+        }
+        let job = |deadline| Job {
+            deadline,
+            _payload: Box::new([0; 8]),
+        };
+
+        let list = LinkedList::<Job>::new();
+        let deque = doubly::LinkedList::<Job>::new();
+        let queue = queue::Queue::<Job>::new();
+        let (g1, g2, g3) = (list.guard(), deque.guard(), queue.guard());
+        assert_eq!(list.front_with(&g1, |job| job.deadline), None);
+        assert_eq!(deque.back_with(&g2, |job| job.deadline), None);
+        assert_eq!(queue.front_with(&g3, |job| job.deadline), None);
+
+        for i in 1..=2 {
+            list.push_back(job(i));
+            deque.push_back(job(i));
+            queue.push_back(job(i));
+        }
+        assert_eq!(list.front_with(&g1, |job| job.deadline), Some(1));
+        assert_eq!(deque.front_with(&g2, |job| job.deadline), Some(1));
+        assert_eq!(deque.back_with(&g2, |job| job.deadline), Some(2));
+        assert_eq!(queue.front_with(&g3, |job| job.deadline), Some(1));
+
+        list.pop_front();
+        deque.pop_back();
+        queue.pop_front();
+        assert_eq!(list.front_with(&g1, |job| job.deadline), Some(2));
+        assert_eq!(deque.back_with(&g2, |job| job.deadline), Some(1));
+        assert_eq!(queue.front_with(&g3, |job| job.deadline), Some(2));
+    }
+
+    #[test]
+    #[should_panic]
+    fn iter_foreign_guard() {
+        let list = LinkedList::<u8>::new();
+        let other = LinkedList::<u8>::new();
+        let guard = other.guard();
+        let _ = list.iter(&guard);
+    }
+
+    #[test]
+    fn shared_collector() {
+        let collector = Arc::new(Collector::new());
+        let a = LinkedList::with_collector(collector.clone());
+        let b = LinkedList::with_collector(collector.clone());
+
+        let guard = a.guard();
+        a.push_back_with(1, &guard);
+        a.push_front_with(0, &guard);
+        b.push_back_with(a.pop_back_with(&guard).unwrap(), &guard);
+        assert_eq!(a.pop_front_if_with(|&i| i > 0, &guard), None);
+        assert_eq!(a.pop_front_with(&guard), Some(0));
+        assert!(b.iter(&guard).eq([1]));
+    }
+
+    #[test]
+    fn iter_multi() {
+        // boxed, so that reading an element its pop already dropped is a
+        // use after free
+        let list = LinkedList::new();
+        for i in 0..ITER {
+            list.push_back(Box::new(i));
+        }
+
+        let b = Barrier::new(THREADS);
+        thread::scope(|s| {
+            for _ in 0..THREADS / 2 {
+                s.spawn(|| {
+                    b.wait();
+                    while list.pop_front().is_some() {}
+                });
+            }
+
+            for _ in 0..THREADS / 2 {
+                s.spawn(|| {
+                    b.wait();
+                    loop {
+                        let guard = list.guard();
+                        let seen: Vec<_> = list.iter(&guard).collect();
+                        assert!(seen.windows(2).all(|w| w[0] < w[1]));
+                        assert!(list.front(&guard).is_none_or(|i| *i < ITER));
+                        assert!(format!("{list:?}").starts_with('['));
+                        if seen.is_empty() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn front() {
+        let list = LinkedList::new();
+        let guard = list.guard();
+        assert_eq!(list.front(&guard), None);
+
+        list.push_back(2);
+        list.push_front(1);
+        assert_eq!(list.front(&guard), Some(1));
+
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.front(&guard), Some(2));
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.front(&guard), None);
+    }
+
+    #[test]
+    fn pop_front_if() {
+        let list = LinkedList::new();
+        assert_eq!(list.pop_front_if(|_| true), None);
+
+        list.push_back(1);
+        list.push_back(2);
+        assert_eq!(list.pop_front_if(|&x| x == 2), None);
+        assert_eq!(list.pop_front_if(|&x| x == 1), Some(1));
+        assert_eq!(list.pop_front_if(|&x| x == 1), None);
+        assert_eq!(list.pop_front(), Some(2));
+    }
+
+    #[test]
+    fn pop_front_if_multi() {
+        testing::pop_front_if_multi(&LinkedList::new());
+    }
+
+    #[test]
+    #[should_panic = "popped an element the same thread is still reading"]
+    #[cfg_attr(miri, ignore = "the panicking pop leaks its node")]
+    fn pop_front_if_reentrant() {
+        testing::pop_front_if_reentrant(&LinkedList::new());
+    }
+
+    #[test]
+    fn collection_traits() {
+        let mut list: LinkedList<i32> = (1..=3).collect();
+        list.extend([4, 5]);
+        assert_eq!(format!("{:?}", list), "[1, 2, 3, 4, 5]");
+        assert_eq!(format!("{:?}", LinkedList::<i32>::default()), "[]");
+
+        assert!(list.into_iter().rev().eq((1..=5).rev()));
+
+        let mut list = LinkedList::new();
+        list.push_front(0);
+        assert_eq!(list.pop_front(), Some(0));
+        list.extend(1..=5);
+        assert!(list.into_iter().eq(1..=5));
+    }
+
+    #[test]
+    fn take_all() {
+        let list = LinkedList::new();
+        assert_eq!(list.take_all().next(), None);
+
+        for i in 0..4 {
+            list.push_front(i);
+        }
+        list.push_back(4);
+        assert!(list.take_all().eq([3, 2, 1, 0, 4]));
+        assert_eq!(list.pop_front(), None);
+
+        for i in 0..4 {
+            list.push_front(i);
+        }
+        let all = list.take_all();
+        assert_eq!(all.len(), 4);
+        assert!(all.rev().eq(0..4));
+
+        list.push_back(5);
+        assert_eq!(list.pop_back(), Some(5));
+    }
+
+    #[test]
+    fn take_all_multi() {
+        let list = LinkedList::new();
+        let taken = Mutex::new(Vec::new());
+        let remaining = AtomicUsize::new(THREADS * ITER);
+        let b = Barrier::new(THREADS * 2);
+
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                let (list, b) = (&list, &b);
+                s.spawn(move || {
+                    b.wait();
+                    for i in 0..ITER {
+                        if i % 3 == 0 {
+                            list.push_back(t * ITER + i);
+                        } else {
+                            list.push_front(t * ITER + i);
+                        }
+                    }
+                });
+            }
+
+            for t in 0..THREADS {
+                let (list, b, taken, remaining) = (&list, &b, &taken, &remaining);
+                s.spawn(move || {
+                    let mut local = Vec::new();
+                    b.wait();
+                    while remaining.load(Ordering::Acquire) > 0 {
+                        let before = local.len();
+                        if t % 2 == 0 {
+                            local.extend(list.take_all());
+                        } else {
+                            local.extend(list.pop_front());
+                        }
+                        remaining.fetch_sub(local.len() - before, Ordering::AcqRel);
+                    }
+                    taken.lock().unwrap().extend(local);
+                });
+            }
+        });
+
+        let taken = taken.into_inner().unwrap();
+        assert_eq!(taken.len(), THREADS * ITER);
+        let unique: HashSet<_> = taken.into_iter().collect();
+        assert_eq!(unique, (0..THREADS * ITER).collect());
+        assert_eq!(list.take_all().len(), 0);
+    }
+
+    #[test]
+    fn tail_hint() {
+        let mut list = LinkedList::new();
+        // only read once `debug_nodes` has checked that the hint is in the list
+        let hint = |list: &mut LinkedList<i32>| {
+            list.debug_nodes();
+            let tail = list.tail.load(Ordering::Acquire);
+            (!tail.is_null()).then(|| *unsafe { (&*tail).inner.get() })
+        };
+
+        list.push_front(0);
+        assert_eq!(hint(&mut list), None);
+        list.push_back(1);
+        list.push_back(2);
+        assert_eq!(hint(&mut list), Some(2));
+
+        // pushes to the front don't invalidate the hint, popping the hinted
+        // node clears it
+        list.push_front(-1);
+        assert_eq!(list.pop_front(), Some(-1));
+        assert_eq!(hint(&mut list), Some(2));
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(hint(&mut list), None);
+
+        list.push_back(3);
+        assert!(list.take_all().eq([0, 1, 3]));
+        assert_eq!(hint(&mut list), None);
+
+        list.extend([4, 5]);
+        assert_eq!(hint(&mut list), Some(5));
+        list.push_back(6);
+        assert!(list.iter(&list.guard()).eq(4..=6));
+    }
+
+    #[test]
+    fn model() {
+        const SEEDS: u64 = if cfg!(miri) { 2 } else { 64 };
+        const OPS: u32 = if cfg!(miri) { 100 } else { 1000 };
+
+        for seed in 1..=SEEDS {
+            let mut rng = Rng(seed);
+            let list = LinkedList::new();
+            let mut model = VecDeque::new();
+
+            for i in 0..OPS {
+                match rng.next() % 9 {
+                    0 | 1 => {
+                        list.push_front(i);
+                        model.push_front(i);
+                    }
+                    2..=4 => {
+                        list.push_back(i);
+                        model.push_back(i);
+                    }
+                    5 | 6 => assert_eq!(list.pop_front(), model.pop_front(), "seed {seed}"),
+                    7 => assert_eq!(list.pop_back(), model.pop_back(), "seed {seed}"),
+                    _ if i % 5 == 0 => assert!(list.take_all().eq(model.drain(..)), "seed {seed}"),
+                    _ => {}
+                }
+            }
+
+            assert!(list.into_iter().eq(model), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn push_back_multi() {
+        let list = LinkedList::new();
+        let b = Barrier::new(THREADS);
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                let (list, b) = (&list, &b);
+                s.spawn(move || {
+                    b.wait();
+                    for i in 0..ITER * 10 {
+                        list.push_back((t, i));
+                    }
+                });
+            }
+        });
+
+        let values: Vec<_> = list.into_iter().collect();
+        assert_eq!(values.len(), THREADS * ITER * 10);
+        for t in 0..THREADS {
+            let mine: Vec<_> = values.iter().filter(|(u, _)| *u == t).collect();
+            assert!(mine.iter().map(|(_, i)| *i).eq(0..ITER * 10));
+        }
+    }
+
+    #[test]
+    fn send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<LinkedList<u8>>();
+        assert_send_sync::<LinkedList<std::sync::mpsc::Sender<u8>>>();
+    }
+}
+
+#[cfg(all(test, loom))]
+mod models {
+    use super::*;
+    use crate::reclaim::CheckedReclaimer;
+    use crate::testing::models::{race, Checked};
+
+    type CheckedList = LinkedList<u32, CheckedReclaimer>;
+
+    #[test]
+    fn push_both_ends() {
+        loom::model(|| {
+            let list = CheckedList::with_values(&[0]);
+            race(&list, |l| l.push_front(1), |l| l.push_back(2));
+            assert_eq!(list.drain(), [1, 0, 2]);
+        });
+    }
+
+    #[test]
+    fn push_back_push_back() {
+        loom::model(|| {
+            let list = CheckedList::with_values(&[0]);
+            race(&list, |l| l.push_back(1), |l| l.push_back(2));
+            let popped = list.drain();
+            assert!(popped == [0, 1, 2] || popped == [0, 2, 1]);
+        });
+    }
+
+    #[test]
+    fn pop_front_pop_front() {
+        loom::model(|| {
+            let list = CheckedList::with_values(&[1, 2]);
+            let (a, b) = race(&list, |l| l.pop_front(), |l| l.pop_front());
+            assert!(matches!((a, b), (Some(1), Some(2)) | (Some(2), Some(1))));
+            assert!(list.is_empty());
+        });
+    }
+
+    #[test]
+    fn pop_both_ends_last() {
+        loom::model(|| {
+            let list = CheckedList::with_values(&[1]);
+            let (front, back) = race(&list, |l| l.pop_front(), |l| l.pop_back());
+            assert!(matches!((front, back), (Some(1), None) | (None, Some(1))));
+            assert!(list.is_empty());
+        });
+    }
+
+    // the pushed node is handed over as the `tail` hint while it may already
+    // be popped, only the second of the two may retire it
+    #[test]
+    fn push_back_pop_back() {
+        loom::model(|| {
+            let list = CheckedList::with_values(&[1]);
+            let (_, popped) = race(&list, |l| l.push_back(2), |l| l.pop_back());
+            match popped {
+                Some(1) => assert_eq!(list.drain(), [2]),
+                Some(2) => assert_eq!(list.drain(), [1]),
+                _ => unreachable!(),
+            }
+            list.push_back(3);
+            assert_eq!(list.drain(), [3]);
+        });
+    }
+
+    #[test]
+    fn take_all_push_back() {
+        loom::model(|| {
+            let list = CheckedList::with_values(&[1]);
+            let (_, taken) = race(
+                &list,
+                |l| l.push_back(2),
+                |l| l.take_all().collect::<Vec<_>>(),
+            );
+            let mut all = taken;
+            all.extend(list.drain());
+            assert_eq!(all, [1, 2]);
+        });
+    }
 }
@@ -0,0 +1,195 @@
+use std::collections::VecDeque;
+use std::mem;
+use std::sync::atomic::{self, AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "async")]
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, Thread};
+use std::time::Instant;
+
+// Threads and tasks blocked until a container has something for them.
+// Notifiers only read `len` unless someone is actually waiting, so the lock
+// stays off the fast path.
+pub(crate) struct WaitList {
+    len: AtomicUsize,
+    waiters: Mutex<VecDeque<Arc<Waiter>>>,
+}
+
+pub(crate) struct Waiter {
+    unpark: Unpark,
+    notified: AtomicBool,
+}
+
+impl Waiter {
+    fn wake(&self) {
+        self.notified.store(true, Ordering::Release);
+        match &self.unpark {
+            Unpark::Thread(thread) => thread.unpark(),
+            #[cfg(feature = "async")]
+            Unpark::Waker(waker) => waker.wake_by_ref(),
+        }
+    }
+}
+
+enum Unpark {
+    Thread(Thread),
+    #[cfg(feature = "async")]
+    Waker(Waker),
+}
+
+impl WaitList {
+    pub(crate) fn new() -> Self {
+        Self {
+            len: AtomicUsize::new(0),
+            waiters: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Calls `attempt` until it succeeds, parking the thread in between.
+    ///
+    /// Returns `None` if `deadline` passes first.
+    pub(crate) fn wait<T>(
+        &self,
+        deadline: Option<Instant>,
+        mut attempt: impl FnMut() -> Option<T>,
+    ) -> Option<T> {
+        loop {
+            if let Some(t) = attempt() {
+                return Some(t);
+            }
+
+            let waiter = self.register(Unpark::Thread(thread::current()));
+            if let Some(t) = attempt() {
+                self.unregister(&waiter);
+                return Some(t);
+            }
+
+            while !waiter.notified.load(Ordering::Acquire) {
+                match deadline {
+                    None => thread::park(),
+                    Some(deadline) => {
+                        let now = Instant::now();
+                        if now >= deadline {
+                            self.unregister(&waiter);
+                            return attempt();
+                        }
+                        thread::park_timeout(deadline - now);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The task equivalent of [`WaitList::wait`], keeping the registration
+    /// in `slot` between polls.
+    ///
+    /// Whoever owns `slot` must hand it to [`WaitList::unregister`] if it
+    /// stops polling before getting `Ready`.
+    #[cfg(feature = "async")]
+    pub(crate) fn poll<T>(
+        &self,
+        cx: &mut Context<'_>,
+        slot: &mut Option<Arc<Waiter>>,
+        mut attempt: impl FnMut() -> Option<T>,
+    ) -> Poll<T> {
+        // whether or not we were woken, we are about to try again, so there
+        // is no notification to hand on
+        if let Some(waiter) = slot.take() {
+            self.cancel(&waiter);
+        }
+
+        if let Some(t) = attempt() {
+            return Poll::Ready(t);
+        }
+
+        let waiter = self.register(Unpark::Waker(cx.waker().clone()));
+        if let Some(t) = attempt() {
+            self.unregister(&waiter);
+            return Poll::Ready(t);
+        }
+
+        *slot = Some(waiter);
+        Poll::Pending
+    }
+
+    /// Wakes one waiting thread or task, if there is any.
+    ///
+    /// Must be called after the state the waiter is looking for has been
+    /// published.
+    #[inline]
+    pub(crate) fn notify_one(&self) {
+        // pairs with the fence in `register`, either we see the waiter or
+        // its second attempt sees our change
+        atomic::fence(Ordering::SeqCst);
+        if self.len.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+
+        let waiter = {
+            let mut waiters = self.waiters.lock().unwrap();
+            let waiter = waiters.pop_front();
+            if waiter.is_some() {
+                self.len.fetch_sub(1, Ordering::Relaxed);
+            }
+            waiter
+        };
+
+        if let Some(waiter) = waiter {
+            waiter.wake();
+        }
+    }
+
+    /// Wakes every waiting thread and task.
+    pub(crate) fn notify_all(&self) {
+        atomic::fence(Ordering::SeqCst);
+        if self.len.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+
+        let waiters = {
+            let mut waiters = self.waiters.lock().unwrap();
+            self.len.store(0, Ordering::Relaxed);
+            mem::take(&mut *waiters)
+        };
+
+        for waiter in waiters {
+            waiter.wake();
+        }
+    }
+
+    /// Removes a waiter that gave up, passing on a notification it received
+    /// but did not act on.
+    pub(crate) fn unregister(&self, waiter: &Arc<Waiter>) {
+        // the notification may have been for state someone else still has to
+        // pick up
+        if !self.cancel(waiter) {
+            self.notify_one();
+        }
+    }
+
+    fn register(&self, unpark: Unpark) -> Arc<Waiter> {
+        let waiter = Arc::new(Waiter {
+            unpark,
+            notified: AtomicBool::new(false),
+        });
+
+        let mut waiters = self.waiters.lock().unwrap();
+        waiters.push_back(waiter.clone());
+        self.len.fetch_add(1, Ordering::Relaxed);
+        drop(waiters);
+
+        atomic::fence(Ordering::SeqCst);
+        waiter
+    }
+
+    // Returns `false` if the waiter was already notified.
+    fn cancel(&self, waiter: &Arc<Waiter>) -> bool {
+        let mut waiters = self.waiters.lock().unwrap();
+        let position = waiters.iter().position(|w| Arc::ptr_eq(w, waiter));
+        if let Some(position) = position {
+            waiters.remove(position);
+            self.len.fetch_sub(1, Ordering::Relaxed);
+        }
+        position.is_some()
+    }
+}
@@ -1,45 +1,134 @@
-use seize::{reclaim, AtomicPtr, Collector, Guard, Linked};
-use std::{mem::ManuallyDrop, ptr};
-use std::{
-    mem::MaybeUninit,
-    sync::atomic::{AtomicUsize, Ordering},
-};
-
-pub struct Queue<T> {
-    head: AtomicPtr<Node<T>>,
-    tail: AtomicPtr<Node<T>>,
+use crate::builder::{Build, Builder};
+#[cfg(feature = "instrument")]
+use crate::instrument::Hook;
+use crate::reclaim::{Collector, DefaultReclaimer, Reclaimer, SeizeReclaimer};
+use crate::slot::Slot;
+use crate::sync::{AtomicPtr, AtomicUsize, Ordering};
+use crate::{assert_guard, parking::WaitList};
+use std::error;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{fmt, ptr};
+
+#[cfg(feature = "async")]
+mod stream;
+
+#[cfg(feature = "async")]
+pub use stream::{Consumer, PopFront, Producer};
+
+pub struct Queue<T, R: Reclaimer = DefaultReclaimer> {
+    head: AtomicPtr<R::Linked<Node<T, R>>>,
+    tail: AtomicPtr<R::Linked<Node<T, R>>>,
+    // slots reserved by pushes, released once a pop has unlinked the node
     len: AtomicUsize,
-    collector: Collector,
+    capacity: Option<usize>,
+    pub(crate) consumers: WaitList,
+    // only used when bounded, by producers waiting for a free slot
+    pub(crate) producers: WaitList,
+    reclaimer: R,
+    #[cfg(feature = "instrument")]
+    hook: Option<Arc<dyn Hook>>,
 }
 
+unsafe impl<T: Send, R: Reclaimer> Send for Queue<T, R> {}
+unsafe impl<T: Send + Sync, R: Reclaimer> Sync for Queue<T, R> {}
+
+type NodePtr<T, R> = *mut <R as Reclaimer>::Linked<Node<T, R>>;
+
 #[derive(Debug)]
-pub struct Node<T> {
-    inner: MaybeUninit<ManuallyDrop<T>>,
-    next: AtomicPtr<Node<T>>,
-    prev: AtomicPtr<Node<T>>,
+pub struct Node<T, R: Reclaimer> {
+    inner: Slot<T>,
+    next: AtomicPtr<R::Linked<Node<T, R>>>,
+    prev: AtomicPtr<R::Linked<Node<T, R>>>,
 }
 
-impl<T> Node<T> {
+impl<T, R: Reclaimer> Node<T, R> {
     fn new(t: T) -> Self {
         Self {
-            inner: MaybeUninit::new(ManuallyDrop::new(t)),
+            inner: Slot::new(t),
             next: AtomicPtr::new(ptr::null_mut()),
             prev: AtomicPtr::new(ptr::null_mut()),
         }
     }
 }
 
+/// The error returned by [`Queue::try_push_back`] when the queue is full,
+/// carrying the value that could not be pushed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Full<T>(pub T);
+
+impl<T> Full<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> fmt::Debug for Full<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Full").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for Full<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("queue is full")
+    }
+}
+
+impl<T> error::Error for Full<T> {}
+
 impl<T> Queue<T> {
     pub fn new() -> Self {
+        Self::with_bound(None, DefaultReclaimer::new())
+    }
+
+    /// Creates a queue that retires its nodes into `collector`, which may be
+    /// shared with other containers.
+    pub fn with_collector(collector: Arc<Collector>) -> Self {
+        Self::with_reclaimer(SeizeReclaimer::shared(collector))
+    }
+
+    /// Creates a queue that holds at most `capacity` elements.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_bound(Some(capacity), DefaultReclaimer::new())
+    }
+}
+
+impl<T> Build for Queue<T> {
+    fn from_builder(builder: Builder) -> Self {
+        #[allow(unused_mut)]
+        let mut queue = Self::with_bound(builder.capacity, builder.reclaimer());
+        queue
+            .reclaimer
+            .preallocate::<Node<T, SeizeReclaimer>>(builder.preallocate);
+        #[cfg(feature = "instrument")]
+        {
+            queue.hook = builder.hook;
+        }
+        queue
+    }
+}
+
+impl<T, R: Reclaimer> Queue<T, R> {
+    pub fn with_reclaimer(reclaimer: R) -> Self {
+        Self::with_bound(None, reclaimer)
+    }
+
+    fn with_bound(capacity: Option<usize>, reclaimer: R) -> Self {
         let list = Self {
             head: AtomicPtr::new(ptr::null_mut()),
             tail: AtomicPtr::new(ptr::null_mut()),
-            collector: Collector::new(),
+            reclaimer,
             len: AtomicUsize::new(0),
+            capacity,
+            consumers: WaitList::new(),
+            producers: WaitList::new(),
+            #[cfg(feature = "instrument")]
+            hook: None,
         };
 
-        let sentinel = list.collector.link_boxed(Node {
-            inner: MaybeUninit::uninit(),
+        let sentinel = list.reclaimer.link_boxed(Node {
+            inner: Slot::empty(),
             next: AtomicPtr::new(ptr::null_mut()),
             prev: AtomicPtr::new(ptr::null_mut()),
         });
@@ -50,109 +139,657 @@ impl<T> Queue<T> {
         list
     }
 
+    #[deprecated(
+        note = "the count can be off while operations are in progress, use `approximate_len`"
+    )]
     pub fn len(&self) -> usize {
+        self.approximate_len()
+    }
+
+    /// Returns the number of elements, counting a push from when it reserves
+    /// its slot and a pop until after its node is unlinked.
+    ///
+    /// The count is at least the length at the moment it is read, and at
+    /// most that plus the number of pushes and pops in progress. It is exact
+    /// while no operation is, and never exceeds the capacity.
+    pub fn approximate_len(&self) -> usize {
         self.len.load(Ordering::Acquire)
     }
 
+    /// Returns whether the queue has no elements, as of reading the sentinel's
+    /// `next`.
+    pub fn is_empty(&self) -> bool {
+        let guard = self.reclaimer.enter();
+        let head = R::protect(&guard, &self.head, Ordering::Acquire);
+        unsafe { &*head }.next.load(Ordering::Acquire).is_null()
+    }
+
+    /// Returns the capacity of a bounded queue, or `None` if it is unbounded.
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    /// Enters the queue's reclaimer, for use with [`Queue::iter`] and the
+    /// `*_with` methods.
+    pub fn guard(&self) -> R::Guard<'_> {
+        self.reclaimer.enter()
+    }
+
+    /// Iterates over clones of the elements from front to back.
+    ///
+    /// Nodes stay allocated for as long as `guard` is held, elements pushed or
+    /// popped concurrently may or may not be observed. The elements are
+    /// cloned because a pop may move them out at any time, use
+    /// [`Iter::next_with`] to look at them in place, which does not need
+    /// `T: Clone`. A pop of an element that is being cloned or looked at
+    /// spins until that is done.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this queue's reclaimer.
+    pub fn iter<'g>(&'g self, guard: &'g R::Guard<'_>) -> Iter<'g, T, R> {
+        assert_guard(guard, &self.reclaimer);
+
+        let head = R::protect(guard, &self.head, Ordering::Acquire);
+        Iter {
+            current: R::protect(guard, &unsafe { &*head }.next, Ordering::Acquire),
+            guard: R::reborrow(guard),
+        }
+    }
+
+    /// Returns a clone of the element at the front without removing it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this queue's reclaimer.
+    pub fn front(&self, guard: &R::Guard<'_>) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.front_with(guard, T::clone)
+    }
+
+    /// Calls `f` with the element at the front without removing it, and
+    /// returns what it returns. Unlike [`Queue::front`] this does not need
+    /// `T: Clone`.
+    ///
+    /// A pop of the element on another thread waits for `f` to return, and
+    /// `f` must not pop from the queue itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this queue's reclaimer, or if `f`
+    /// pops the element it is given.
+    pub fn front_with<U>(&self, guard: &R::Guard<'_>, f: impl FnOnce(&T) -> U) -> Option<U> {
+        self.iter(guard).next_with(f)
+    }
+
+    /// Returns the elements front to back, after checking that every node
+    /// links back to the one before it and that `tail` is one of them. For
+    /// looking at the structure of the queue in tests, so only available in
+    /// debug builds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the links are inconsistent.
+    #[cfg(any(test, debug_assertions))]
+    pub fn debug_nodes(&mut self) -> Vec<&T> {
+        let tail = self.tail.load(Ordering::Relaxed);
+        let mut prev = self.head.load(Ordering::Relaxed);
+        let mut found_tail = prev == tail;
+
+        let mut nodes = Vec::new();
+        let mut current = unsafe { &*prev }.next.load(Ordering::Relaxed);
+        while !current.is_null() {
+            let node = unsafe { &*current };
+            assert_eq!(
+                node.prev.load(Ordering::Relaxed),
+                prev,
+                "element {} does not link back to the one before it",
+                nodes.len()
+            );
+            found_tail |= current == tail;
+            nodes.push(unsafe { node.inner.get() });
+
+            prev = current;
+            current = node.next.load(Ordering::Relaxed);
+        }
+        assert!(found_tail, "`tail` is not in the queue");
+        nodes
+    }
+
+    /// Tries to splice the chain `first..=last` in after `onto`.
     #[inline]
     fn push_back_internal(
         &self,
-        onto: *mut Linked<Node<T>>,
-        new: *mut Linked<Node<T>>,
-        guard: &Guard,
+        onto: NodePtr<T, R>,
+        first: NodePtr<T, R>,
+        last: NodePtr<T, R>,
+        guard: &R::Guard<'_>,
     ) -> bool {
-        let next = guard.protect(&unsafe { &*onto }.next, Ordering::Acquire);
-        unsafe { &*new }.prev.store(onto, Ordering::Release);
+        let next = R::protect(guard, &unsafe { &*onto }.next, Ordering::Acquire);
+        unsafe { &*first }.prev.store(onto, Ordering::Release);
 
         if !next.is_null() {
-            let _ = self
+            if self
                 .tail
-                .compare_exchange(onto, next, Ordering::Acquire, Ordering::Relaxed);
+                .compare_exchange(onto, next, Ordering::Release, Ordering::Relaxed)
+                .is_ok()
+            {
+                instrument!(self, HelpedAdvanceTail);
+            }
 
             false
         } else {
             let result = unsafe { &*onto }
                 .next
-                .compare_exchange(ptr::null_mut(), new, Ordering::Release, Ordering::Relaxed)
+                .compare_exchange(ptr::null_mut(), first, Ordering::Release, Ordering::Relaxed)
                 .is_ok();
 
             if result {
-                let _ = self
-                    .tail
-                    .compare_exchange(onto, new, Ordering::Release, Ordering::Relaxed);
+                let _ =
+                    self.tail
+                        .compare_exchange(onto, last, Ordering::Release, Ordering::Relaxed);
+            } else {
+                instrument!(self, CasLost);
             }
             result
         }
     }
 
     #[inline]
-    fn pop_front_internal(&self, guard: &Guard) -> Result<Option<T>, ()> {
-        let head = guard.protect(&self.head, Ordering::Acquire);
-        let next = guard.protect(&unsafe { &*head }.next, Ordering::Acquire);
+    fn pop_front_internal(
+        &self,
+        guard: &R::Guard<'_>,
+        predicate: Option<&mut (dyn FnMut(&T) -> bool + '_)>,
+    ) -> Result<Option<T>, ()> {
+        let head = R::protect(guard, &self.head, Ordering::Acquire);
+        let next = R::protect(guard, &unsafe { &*head }.next, Ordering::Acquire);
 
         if !next.is_null() {
+            // a pop that won the CAS below would wait for the predicate
+            // before taking the value
+            if let Some(predicate) = predicate {
+                match unsafe { &*next }.inner.read(|t| predicate(t)) {
+                    Some(true) => {}
+                    Some(false) => return Ok(None),
+                    None => return Err(()),
+                }
+            }
+
             match self
                 .head
                 .compare_exchange(head, next, Ordering::Release, Ordering::Relaxed)
             {
                 Ok(_) => {
-                    let tail = guard.protect(&self.tail, Ordering::Release);
-                    if head == tail {
-                        let _ = self.tail.compare_exchange(
-                            tail,
-                            next,
-                            Ordering::Release,
-                            Ordering::Relaxed,
-                        );
+                    let tail = R::protect(guard, &self.tail, Ordering::Acquire);
+                    if head == tail
+                        && self
+                            .tail
+                            .compare_exchange(tail, next, Ordering::Release, Ordering::Relaxed)
+                            .is_ok()
+                    {
+                        instrument!(self, HelpedAdvanceTail);
                     }
 
-                    let data = unsafe { ptr::read(&(*next).inner) };
+                    let data = unsafe { (&*next).inner.take() };
                     Ok(unsafe { self.consume_and_retire(head, data) })
                 }
-                Err(_) => Err(()),
+                Err(_) => {
+                    instrument!(self, CasLost);
+                    Err(())
+                }
             }
         } else {
             Ok(None)
         }
     }
 
+    /// Removes the front element.
+    ///
+    /// While another thread looks at the element through an iterator or a
+    /// peek, this spins until it is done.
     pub fn pop_front(&self) -> Option<T> {
-        let guard = self.collector.enter();
+        self.pop_front_with(&self.reclaimer.enter())
+    }
+
+    /// Removes the front element only if `predicate` returns `true` for it.
+    ///
+    /// The predicate may be called more than once if the front changes
+    /// concurrently, the element it last accepted is the one returned. Pops
+    /// of the element it is looking at stall on other threads until it
+    /// returns, and it must not pop from this queue itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the predicate pops the element it is looking at, which would
+    /// otherwise wait for the predicate forever. That element is leaked.
+    pub fn pop_front_if(&self, predicate: impl FnMut(&T) -> bool) -> Option<T> {
+        self.pop_front_if_with(predicate, &self.reclaimer.enter())
+    }
+
+    /// Like [`Queue::pop_front`], under a guard the caller already holds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this queue's reclaimer.
+    pub fn pop_front_with(&self, guard: &R::Guard<'_>) -> Option<T> {
+        self.pop_front_matching(None, guard)
+    }
+
+    /// Like [`Queue::pop_front_if`], under a guard the caller already holds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this queue's reclaimer.
+    pub fn pop_front_if_with(
+        &self,
+        mut predicate: impl FnMut(&T) -> bool,
+        guard: &R::Guard<'_>,
+    ) -> Option<T> {
+        self.pop_front_matching(Some(&mut predicate), guard)
+    }
+
+    // Without a predicate any element matches, and the value is not read
+    // before the CAS on `head` is won.
+    fn pop_front_matching(
+        &self,
+        mut predicate: Option<&mut dyn FnMut(&T) -> bool>,
+        guard: &R::Guard<'_>,
+    ) -> Option<T> {
+        assert_guard(guard, &self.reclaimer);
         loop {
-            if let Ok(head) = self.pop_front_internal(&guard) {
+            if let Ok(head) = self.pop_front_internal(guard, predicate.as_deref_mut()) {
                 return head;
             }
         }
     }
 
+    /// Removes up to `max` elements from the front with a single CAS on
+    /// `head`, appending them to `out` in order. Returns how many were taken.
+    pub fn pop_front_batch(&self, max: usize, out: &mut Vec<T>) -> usize {
+        if max == 0 {
+            return 0;
+        }
+
+        let guard = self.reclaimer.enter();
+        let (head, last, taken) = loop {
+            let head = R::protect(&guard, &self.head, Ordering::Acquire);
+
+            let (mut last, mut taken) = (head, 0);
+            while taken < max {
+                let next = R::protect(&guard, &unsafe { &*last }.next, Ordering::Acquire);
+                if next.is_null() {
+                    break;
+                }
+                (last, taken) = (next, taken + 1);
+            }
+
+            if taken == 0 {
+                return 0;
+            }
+
+            if self
+                .head
+                .compare_exchange(head, last, Ordering::Release, Ordering::Relaxed)
+                .is_ok()
+            {
+                break (head, last, taken);
+            }
+            instrument!(self, CasLost);
+        };
+
+        // as in `pop_front_internal`, `tail` must not be left pointing at a
+        // node we are about to retire
+        loop {
+            let tail = R::protect(&guard, &self.tail, Ordering::Acquire);
+            if !Self::chain_contains(head, last, tail) {
+                break;
+            }
+            if self
+                .tail
+                .compare_exchange(tail, last, Ordering::Release, Ordering::Relaxed)
+                .is_ok()
+            {
+                instrument!(self, HelpedAdvanceTail);
+            }
+        }
+
+        // `last` stays behind as the new sentinel, everything before it goes
+        out.reserve(taken);
+        let mut current = head;
+        while current != last {
+            let next = unsafe { &*current }.next.load(Ordering::Acquire);
+            out.push(unsafe { (&*next).inner.take() });
+            unsafe { self.reclaimer.retire(current) };
+            current = next;
+        }
+
+        self.len.fetch_sub(taken, Ordering::Release);
+        if self.capacity.is_some() {
+            self.producers.notify_all();
+        }
+        taken
+    }
+
+    // Whether `node` is one of `first..last`, not counting `last`.
+    fn chain_contains(first: NodePtr<T, R>, last: NodePtr<T, R>, node: NodePtr<T, R>) -> bool {
+        let mut current = first;
+        while current != last {
+            if current == node {
+                return true;
+            }
+            current = unsafe { &*current }.next.load(Ordering::Acquire);
+        }
+        false
+    }
+
+    /// Removes the front element, parking the thread until one is available.
+    pub fn pop_front_blocking(&self) -> T {
+        self.consumers.wait(None, || self.pop_front()).unwrap()
+    }
+
+    /// Removes the front element, parking the thread for at most `timeout`
+    /// until one is available.
+    pub fn pop_front_timeout(&self, timeout: Duration) -> Option<T> {
+        let deadline = Instant::now().checked_add(timeout);
+        self.consumers.wait(deadline, || self.pop_front())
+    }
+
+    /// Appends an element to the back of the queue.
+    ///
+    /// If the queue is bounded and full this parks the thread until a
+    /// consumer makes room, see [`Queue::try_push_back`] for a non-blocking
+    /// alternative.
     #[inline]
     pub fn push_back(&self, t: T) {
-        let guard = self.collector.enter();
-        let new = self.collector.link_boxed(Node::new(t));
+        if !self.try_reserve(1) {
+            self.producers
+                .wait(None, || self.try_reserve(1).then_some(()));
+        }
+        self.link_back(t);
+    }
+
+    /// Like [`Queue::push_back`], under a guard the caller already holds.
+    ///
+    /// The guard stays held while waiting for room in a full queue, which
+    /// holds back reclamation, so prefer [`Queue::try_push_back_with`] on
+    /// bounded queues.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this queue's reclaimer.
+    pub fn push_back_with(&self, t: T, guard: &R::Guard<'_>) {
+        assert_guard(guard, &self.reclaimer);
+        if !self.try_reserve(1) {
+            self.producers
+                .wait(None, || self.try_reserve(1).then_some(()));
+        }
+        self.link_back_with(t, guard);
+    }
+
+    /// Appends all elements of `iter` to the back of the queue at once.
+    ///
+    /// The elements are linked into a private chain first, which is then
+    /// spliced in with a single CAS, so they end up next to each other.
+    ///
+    /// # Panics
+    ///
+    /// On a bounded queue this waits for room for the whole batch, and panics
+    /// if the batch is larger than the capacity.
+    pub fn push_back_batch(&self, iter: impl IntoIterator<Item = T>) {
+        let mut iter = iter.into_iter();
+        let Some(t) = iter.next() else {
+            return;
+        };
+
+        let first = self.reclaimer.link_boxed(Node::new(t));
+        let (mut last, mut len) = (first, 1);
+        for t in iter {
+            let new = self.reclaimer.link_boxed(Node::new(t));
+            unsafe { &*new }.prev.store(last, Ordering::Relaxed);
+            unsafe { &*last }.next.store(new, Ordering::Relaxed);
+            (last, len) = (new, len + 1);
+        }
+
+        if let Some(capacity) = self.capacity {
+            if len > capacity {
+                unsafe { self.free_chain(first) };
+                panic!("batch is larger than the queue capacity");
+            }
+            if !self.try_reserve(len) {
+                self.producers
+                    .wait(None, || self.try_reserve(len).then_some(()));
+            }
+        } else {
+            self.try_reserve(len);
+        }
+
+        self.splice_back(first, last, &self.reclaimer.enter());
+        self.consumers.notify_all();
+    }
+
+    /// Appends an element to the back of the queue, or hands it back if the
+    /// queue is at capacity.
+    #[inline]
+    pub fn try_push_back(&self, t: T) -> Result<(), Full<T>> {
+        if !self.try_reserve(1) {
+            return Err(Full(t));
+        }
+        self.link_back(t);
+        Ok(())
+    }
+
+    /// Like [`Queue::try_push_back`], under a guard the caller already holds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `guard` was not entered on this queue's reclaimer.
+    #[inline]
+    pub fn try_push_back_with(&self, t: T, guard: &R::Guard<'_>) -> Result<(), Full<T>> {
+        assert_guard(guard, &self.reclaimer);
+        if !self.try_reserve(1) {
+            return Err(Full(t));
+        }
+        self.link_back_with(t, guard);
+        Ok(())
+    }
+
+    /// Reserves slots for `n` elements, failing if the queue does not have
+    /// room for all of them.
+    #[inline]
+    pub(crate) fn try_reserve(&self, n: usize) -> bool {
+        match self.capacity {
+            None => {
+                self.len.fetch_add(n, Ordering::AcqRel);
+                true
+            }
+            Some(capacity) => self
+                .len
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |len| {
+                    (n <= capacity - len).then_some(len + n)
+                })
+                .is_ok(),
+        }
+    }
+
+    /// Links a new node at the back, into a slot that was already reserved.
+    #[inline]
+    pub(crate) fn link_back(&self, t: T) {
+        self.link_back_with(t, &self.reclaimer.enter());
+    }
+
+    #[inline]
+    fn link_back_with(&self, t: T, guard: &R::Guard<'_>) {
+        let new = self.reclaimer.link_boxed(Node::new(t));
+        self.splice_back(new, new, guard);
+        self.consumers.notify_one();
+    }
+
+    #[inline]
+    fn splice_back(&self, first: NodePtr<T, R>, last: NodePtr<T, R>, guard: &R::Guard<'_>) {
         loop {
-            let tail = guard.protect(&self.tail, Ordering::Acquire);
-            if self.push_back_internal(tail, new, &guard) {
-                self.len.fetch_add(1, Ordering::Release);
+            let tail = R::protect(guard, &self.tail, Ordering::Acquire);
+            if self.push_back_internal(tail, first, last, guard) {
                 break;
             }
         }
     }
 
+    /// Gives back a slot reserved with [`Queue::try_reserve`] that will not
+    /// be filled.
+    #[cfg(feature = "async")]
     #[inline]
-    unsafe fn consume_and_retire(
-        &self,
-        ptr: *mut Linked<Node<T>>,
-        data: MaybeUninit<ManuallyDrop<T>>,
-    ) -> Option<T> {
-        self.collector.retire(ptr, reclaim::boxed::<Node<T>>);
+    pub(crate) fn release(&self) {
+        self.len.fetch_sub(1, Ordering::Release);
+        self.notify_producers();
+    }
+
+    #[inline]
+    fn notify_producers(&self) {
+        if self.capacity.is_some() {
+            self.producers.notify_one();
+        }
+    }
+
+    /// Drops the elements of a chain that was never published and frees its
+    /// nodes.
+    unsafe fn free_chain(&self, mut current: NodePtr<T, R>) {
+        while !current.is_null() {
+            let node = &mut *current;
+            let next = node.next.load(Ordering::Relaxed);
+            node.inner.drop_value();
+            self.reclaimer.free(current);
+            current = next;
+        }
+    }
+
+    #[inline]
+    unsafe fn consume_and_retire(&self, ptr: NodePtr<T, R>, data: T) -> Option<T> {
+        self.reclaimer.retire(ptr);
         self.len.fetch_sub(1, Ordering::Release);
-        return Some(ManuallyDrop::into_inner(data.assume_init()));
+        self.notify_producers();
+        Some(data)
+    }
+}
+
+impl<T> Default for Queue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<T> for Queue<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T, R: Reclaimer> Extend<T> for Queue<T, R> {
+    /// # Panics
+    ///
+    /// Panics if a bounded queue runs out of room, as no consumer could make
+    /// room while the queue is borrowed mutably. The elements pushed before
+    /// stay in the queue.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for t in iter {
+            if self.try_push_back(t).is_err() {
+                panic!("extended past the queue capacity");
+            }
+        }
+    }
+}
+
+impl<T, R: Reclaimer> IntoIterator for Queue<T, R> {
+    type Item = T;
+    type IntoIter = IntoIter<T, R>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
     }
 }
 
-impl<T> Drop for Queue<T> {
+/// An owning iterator that pops elements from the front.
+pub struct IntoIter<T, R: Reclaimer = DefaultReclaimer>(Queue<T, R>);
+
+impl<T, R: Reclaimer> Iterator for IntoIter<T, R> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_front()
+    }
+}
+
+impl<T: fmt::Debug, R: Reclaimer> fmt::Debug for Queue<T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let guard = self.guard();
+        let (mut iter, mut list) = (self.iter(&guard), f.debug_list());
+        // formatted in place, a pop of an element waits for it to be done
+        while iter
+            .next_with(|t| {
+                list.entry(t);
+            })
+            .is_some()
+        {}
+        list.finish()
+    }
+}
+
+/// The iterator returned by [`Queue::iter`], which only implements
+/// [`Iterator`] for `T: Clone`.
+pub struct Iter<'g, T, R: Reclaimer + 'g = DefaultReclaimer> {
+    current: NodePtr<T, R>,
+    guard: &'g R::Guard<'g>,
+}
+
+impl<'g, T: 'g, R: Reclaimer> Iter<'g, T, R> {
+    fn next_slot(&mut self) -> Option<&'g Slot<T>> {
+        if self.current.is_null() {
+            return None;
+        }
+
+        let node = unsafe { &*self.current };
+        self.current = R::protect(self.guard, &node.next, Ordering::Acquire);
+        Some(&node.inner)
+    }
+
+    /// Calls `f` with the next element in place and returns what it returns,
+    /// which also works for elements that are not `Clone`.
+    ///
+    /// A pop of the element on another thread waits for `f` to return, and
+    /// `f` must not pop from the queue itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` pops the element it is given.
+    pub fn next_with<U>(&mut self, f: impl FnOnce(&T) -> U) -> Option<U> {
+        let mut f = Some(f);
+        // elements popped since their node was reached are skipped
+        while let Some(slot) = self.next_slot() {
+            // `read` only calls `f` if the element is still there
+            if let Some(u) = slot.read(|t| f.take().unwrap()(t)) {
+                return Some(u);
+            }
+        }
+        None
+    }
+}
+
+impl<'g, T: Clone + 'g, R: Reclaimer> Iterator for Iter<'g, T, R> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with(T::clone)
+    }
+}
+
+impl<T, R: Reclaimer> Drop for Queue<T, R> {
     fn drop(&mut self) {
         while self.pop_front().is_some() {}
+        // the last node to be popped stays behind as the sentinel
+        unsafe { self.reclaimer.free(self.head.load(Ordering::Relaxed)) };
     }
 }
 
@@ -161,6 +798,7 @@ mod tests {
     use std::{sync::Barrier, thread, time::Duration};
 
     use super::*;
+    use crate::testing;
 
     #[test]
     fn push_back_pop_front() {
@@ -168,15 +806,37 @@ mod tests {
         list.push_back(1);
         list.push_back(2);
         list.push_back(3);
-        assert_eq!(list.len(), 3);
+        assert_eq!(list.approximate_len(), 3);
         assert_eq!(list.pop_front().unwrap(), 1);
         assert_eq!(list.pop_front().unwrap(), 2);
         assert_eq!(list.pop_front().unwrap(), 3);
         assert!(list.pop_front().is_none());
-        assert_eq!(list.len(), 0);
+        assert_eq!(list.approximate_len(), 0);
+    }
+
+    const ITER: u32 = if cfg!(miri) { 20 } else { 100 };
+    const THREADS: usize = 4;
+
+    #[test]
+    fn len() {
+        let list = Queue::new();
+        assert!(list.is_empty());
+        list.push_back(1);
+        list.push_back(2);
+        assert_eq!(list.approximate_len(), 2);
+        assert!(!list.is_empty());
+
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.approximate_len(), 1);
+        assert_eq!(list.pop_front(), Some(2));
+        assert_eq!(list.approximate_len(), 0);
+        assert!(list.is_empty());
     }
 
-    const ITER: u32 = 100;
+    #[test]
+    fn len_bounds() {
+        testing::len_bounds(&Queue::new());
+    }
 
     #[test]
     fn push_pop_multi() {
@@ -192,12 +852,605 @@ mod tests {
 
             s.spawn(|| {
                 b.wait();
+                let mut popped = 0;
+                while popped < ITER {
+                    popped += u32::from(list.pop_front().is_some());
+                }
+            });
+        });
+        assert_eq!(list.approximate_len(), 0);
+    }
+
+    #[cfg(feature = "instrument")]
+    mod instrument {
+        use std::cell::RefCell;
+        use std::sync::Once;
+
+        use super::*;
+        use crate::instrument::{set_hook, Event};
+
+        thread_local! {
+            static EVENTS: RefCell<Vec<Event>> = const { RefCell::new(Vec::new()) };
+        }
+
+        fn record() {
+            static HOOK: Once = Once::new();
+            HOOK.call_once(|| {
+                let hook = |e| EVENTS.with(|events| events.borrow_mut().push(e));
+                assert!(set_hook(Box::new(hook)).is_ok());
+            });
+            EVENTS.with(|events| events.borrow_mut().clear());
+        }
+
+        fn events() -> Vec<Event> {
+            EVENTS.with(|events| events.borrow_mut().drain(..).collect())
+        }
+
+        // links a node after the current tail without advancing `tail`, as
+        // if the pushing thread had stalled right after its CAS on `next`
+        fn push_lagging(queue: &Queue<u32>, t: u32) {
+            let guard = queue.reclaimer.enter();
+            let tail = DefaultReclaimer::protect(&guard, &queue.tail, Ordering::Acquire);
+            let new = queue.reclaimer.link_boxed(Node::new(t));
+            unsafe { &*new }.prev.store(tail, Ordering::Release);
+            unsafe { &*tail }.next.store(new, Ordering::Release);
+            queue.len.fetch_add(1, Ordering::Release);
+        }
+
+        #[test]
+        fn push_helps_lagging_tail() {
+            record();
+            let queue = Queue::new();
+            queue.push_back(1);
+            push_lagging(&queue, 2);
+            assert!(events().is_empty());
+
+            queue.push_back(3);
+            assert_eq!(events(), [Event::HelpedAdvanceTail]);
+
+            assert_eq!(queue.pop_front(), Some(1));
+            assert_eq!(queue.pop_front(), Some(2));
+            assert_eq!(queue.pop_front(), Some(3));
+            assert_eq!(queue.pop_front(), None);
+            assert!(events().is_empty());
+        }
+
+        #[test]
+        fn pop_fixes_lagging_tail() {
+            record();
+            let queue = Queue::new();
+            push_lagging(&queue, 1);
+
+            assert_eq!(queue.pop_front(), Some(1));
+            assert_eq!(events(), [Event::HelpedAdvanceTail]);
+
+            queue.push_back(2);
+            assert_eq!(queue.pop_front(), Some(2));
+            assert!(events().is_empty());
+        }
+
+        #[test]
+        fn local_hook() {
+            record();
+            let local = Arc::new(std::sync::Mutex::new(Vec::new()));
+            let queue = {
+                let local = local.clone();
+                Builder::new()
+                    .hook(move |e| local.lock().unwrap().push(e))
+                    .build()
+            };
+            let other = Queue::new();
+
+            push_lagging(&queue, 1);
+            push_lagging(&other, 1);
+            assert_eq!(queue.pop_front(), Some(1));
+            assert_eq!(other.pop_front(), Some(1));
+
+            assert_eq!(*local.lock().unwrap(), [Event::HelpedAdvanceTail]);
+            assert_eq!(events(), [Event::HelpedAdvanceTail; 2]);
+        }
+    }
+
+    #[test]
+    fn iter() {
+        let queue = Queue::new();
+        let guard = queue.guard();
+        assert_eq!(queue.iter(&guard).next(), None);
+
+        queue.push_back(1);
+        queue.push_back(2);
+        queue.push_back(3);
+        assert!(queue.iter(&guard).eq([1, 2, 3]));
+
+        assert_eq!(queue.pop_front(), Some(1));
+        assert!(queue.iter(&guard).eq([2, 3]));
+    }
+
+    #[test]
+    fn iter_concurrent_pops() {
+        // the elements own heap memory, which their pop frees right away
+        let queue = Queue::new();
+        let b = Barrier::new(THREADS);
+        thread::scope(|s| {
+            for _ in 0..THREADS - 1 {
+                s.spawn(|| {
+                    b.wait();
+                    for i in 0..ITER {
+                        queue.push_back(i.to_string());
+                        assert!(queue.pop_front().is_some());
+                    }
+                });
+            }
+
+            b.wait();
+            for _ in 0..ITER {
+                let guard = queue.guard();
+                for i in queue.iter(&guard).chain(queue.front(&guard)) {
+                    assert!(i.parse::<u32>().unwrap() < ITER);
+                }
+                assert!(format!("{queue:?}").starts_with('['));
+            }
+        });
+    }
+
+    #[test]
+    fn front() {
+        let queue = Queue::new();
+        let guard = queue.guard();
+        assert_eq!(queue.front(&guard), None);
+
+        queue.push_back(1);
+        queue.push_back(2);
+        assert_eq!(queue.front(&guard), Some(1));
+
+        assert_eq!(queue.pop_front(), Some(1));
+        assert_eq!(queue.front(&guard), Some(2));
+    }
+
+    #[test]
+    fn shared_collector() {
+        let collector = Arc::new(Collector::new());
+        let a = Queue::with_collector(collector.clone());
+        let b = Queue::with_collector(collector.clone());
+
+        let guard = collector.enter();
+        a.push_back_with(1, &guard);
+        assert_eq!(a.try_push_back_with(2, &guard), Ok(()));
+        b.push_back_with(a.pop_front_with(&guard).unwrap(), &guard);
+        assert_eq!(a.pop_front_if_with(|&i| i > 2, &guard), None);
+        assert!(a.iter(&guard).eq([2]));
+        assert!(b.iter(&guard).eq([1]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn foreign_guard() {
+        let queue = Queue::<u8>::new();
+        let other = Queue::<u8>::new();
+        queue.pop_front_with(&other.guard());
+    }
+
+    #[test]
+    fn shared_collector_multi() {
+        let collector = Arc::new(Collector::new());
+        let mut queues: Vec<_> = (0..THREADS)
+            .map(|_| Queue::with_collector(collector.clone()))
+            .collect();
+        queues[0].extend(0..ITER);
+
+        // every thread passes what it pops on to the next queue, entering
+        // the shared collector once per round
+        let b = Barrier::new(THREADS);
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                let (queues, collector, b) = (&queues, &collector, &b);
+                s.spawn(move || {
+                    b.wait();
+                    for _ in 0..ITER / 10 {
+                        let guard = collector.enter();
+                        for _ in 0..10 {
+                            if let Some(i) = queues[t].pop_front_with(&guard) {
+                                queues[(t + 1) % THREADS].push_back_with(i, &guard);
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        let mut all: Vec<_> = queues.into_iter().flatten().collect();
+        all.sort_unstable();
+        assert_eq!(all, (0..ITER).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn pop_front_if() {
+        let queue = Queue::new();
+        assert_eq!(queue.pop_front_if(|_| true), None);
+
+        queue.push_back(1);
+        queue.push_back(2);
+        assert_eq!(queue.pop_front_if(|&x| x == 2), None);
+        assert_eq!(queue.pop_front_if(|&x| x == 1), Some(1));
+        assert_eq!(queue.pop_front_if(|&x| x == 1), None);
+        assert_eq!(queue.pop_front(), Some(2));
+    }
+
+    #[test]
+    fn pop_front_if_multi() {
+        testing::pop_front_if_multi(&Queue::new());
+    }
+
+    #[test]
+    #[should_panic = "popped an element the same thread is still reading"]
+    #[cfg_attr(miri, ignore = "the panicking pop leaks its node")]
+    fn pop_front_if_reentrant() {
+        testing::pop_front_if_reentrant(&Queue::new());
+    }
+
+    #[test]
+    fn collection_traits() {
+        let mut queue: Queue<i32> = (1..=3).collect();
+        queue.extend([4, 5]);
+        assert_eq!(format!("{:?}", queue), "[1, 2, 3, 4, 5]");
+        assert_eq!(format!("{:?}", Queue::<i32>::default()), "[]");
+        assert!(queue.into_iter().eq(1..=5));
+    }
+
+    #[test]
+    fn try_push_back() {
+        let queue = Queue::with_capacity(2);
+        assert_eq!(queue.capacity(), Some(2));
+        assert_eq!(queue.try_push_back(1), Ok(()));
+        assert_eq!(queue.try_push_back(2), Ok(()));
+        assert_eq!(queue.try_push_back(3), Err(Full(3)));
+        assert_eq!(queue.approximate_len(), 2);
+
+        assert_eq!(queue.pop_front(), Some(1));
+        assert_eq!(queue.try_push_back(3), Ok(()));
+        assert!(queue.iter(&queue.guard()).eq([2, 3]));
+
+        let queue = Queue::with_capacity(0);
+        assert_eq!(queue.try_push_back(1), Err(Full(1)));
+        assert_eq!(Queue::<u8>::new().capacity(), None);
+    }
+
+    #[test]
+    fn bounded_multi() {
+        const CAPACITY: usize = 8;
+
+        let queue = Queue::with_capacity(CAPACITY);
+        let b = Barrier::new(4);
+        thread::scope(|s| {
+            for _ in 0..2 {
+                s.spawn(|| {
+                    b.wait();
+                    for i in 0..ITER {
+                        let mut t = i;
+                        while let Err(Full(rejected)) = queue.try_push_back(t) {
+                            assert!(queue.approximate_len() <= CAPACITY);
+                            t = rejected;
+                        }
+                    }
+                });
+            }
+
+            for _ in 0..2 {
+                s.spawn(|| {
+                    b.wait();
+                    let mut popped = 0;
+                    while popped < ITER {
+                        assert!(queue.approximate_len() <= CAPACITY);
+                        if queue.pop_front().is_some() {
+                            popped += 1;
+                        }
+                    }
+                });
+            }
+        });
+        assert_eq!(queue.approximate_len(), 0);
+    }
+
+    #[test]
+    fn push_back_waits_when_full() {
+        let queue = Queue::with_capacity(1);
+        queue.push_back(1);
+        thread::scope(|s| {
+            s.spawn(|| queue.push_back(2));
+            thread::sleep(Duration::from_millis(10));
+            assert_eq!(queue.pop_front(), Some(1));
+        });
+        assert_eq!(queue.pop_front(), Some(2));
+    }
+
+    #[test]
+    fn pop_front_timeout() {
+        let queue = Queue::new();
+        assert_eq!(queue.pop_front_timeout(Duration::from_millis(10)), None);
+
+        queue.push_back(1);
+        assert_eq!(queue.pop_front_timeout(Duration::from_millis(10)), Some(1));
+
+        thread::scope(|s| {
+            s.spawn(|| {
                 thread::sleep(Duration::from_millis(10));
-                for _ in 0..ITER {
-                    let _ = list.pop_front();
+                queue.push_back(2);
+            });
+            assert_eq!(queue.pop_front_timeout(Duration::from_secs(60)), Some(2));
+        });
+    }
+
+    #[test]
+    fn pop_front_blocking_multi() {
+        const CONSUMERS: u32 = 4;
+
+        let queue = Queue::new();
+        let b = Barrier::new(CONSUMERS as usize + 1);
+        let sum = thread::scope(|s| {
+            let handles: Vec<_> = (0..CONSUMERS)
+                .map(|_| {
+                    s.spawn(|| {
+                        b.wait();
+                        (0..ITER / CONSUMERS)
+                            .map(|_| queue.pop_front_blocking())
+                            .sum::<u32>()
+                    })
+                })
+                .collect();
+
+            b.wait();
+            for i in 0..ITER {
+                if i % 10 == 0 {
+                    thread::sleep(Duration::from_millis(1));
                 }
+                queue.push_back(i);
+            }
+
+            handles.into_iter().map(|h| h.join().unwrap()).sum::<u32>()
+        });
+        assert_eq!(sum, (0..ITER).sum());
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn batch() {
+        let mut queue = Queue::new();
+        queue.push_back(0);
+        queue.push_back_batch(1..5);
+        queue.push_back_batch([]);
+        queue.push_back(5);
+        assert_eq!(queue.approximate_len(), 6);
+        assert!(queue.iter(&queue.guard()).eq(0..6));
+        assert_eq!(queue.debug_nodes(), [&0, &1, &2, &3, &4, &5]);
+
+        let mut out = Vec::new();
+        assert_eq!(queue.pop_front_batch(0, &mut out), 0);
+        assert_eq!(queue.pop_front_batch(4, &mut out), 4);
+        assert_eq!(out, [0, 1, 2, 3]);
+        assert_eq!(queue.pop_front_batch(4, &mut out), 2);
+        assert_eq!(out, [0, 1, 2, 3, 4, 5]);
+        assert_eq!(queue.pop_front_batch(4, &mut out), 0);
+        assert_eq!(queue.approximate_len(), 0);
+
+        // the tail has to move off the detached nodes before they are retired
+        queue.push_back_batch(6..9);
+        assert_eq!(queue.pop_front_batch(8, &mut out), 3);
+        assert!(queue.debug_nodes().is_empty());
+        queue.push_back(9);
+        assert_eq!(queue.pop_front(), Some(9));
+    }
+
+    #[test]
+    fn batch_bounded() {
+        let queue = Queue::with_capacity(4);
+        queue.push_back(0);
+        thread::scope(|s| {
+            s.spawn(|| queue.push_back_batch(1..5));
+            thread::sleep(Duration::from_millis(10));
+            assert_eq!(queue.approximate_len(), 1);
+            assert_eq!(queue.pop_front(), Some(0));
+        });
+        assert_eq!(queue.approximate_len(), 4);
+        assert_eq!(queue.try_push_back(5), Err(Full(5)));
+    }
+
+    #[test]
+    #[should_panic = "batch is larger than the queue capacity"]
+    fn batch_over_capacity() {
+        Queue::with_capacity(2).push_back_batch(0..3);
+    }
+
+    #[test]
+    fn extend_bounded() {
+        let mut queue = Queue::with_capacity(3);
+        queue.push_back(0);
+        queue.extend(1..3);
+        assert!(queue.into_iter().eq(0..3));
+    }
+
+    #[test]
+    #[should_panic = "extended past the queue capacity"]
+    fn extend_over_capacity() {
+        Queue::with_capacity(2).extend(0..3);
+    }
+
+    #[test]
+    fn batch_multi() {
+        const PRODUCERS: u32 = 4;
+        const BATCH: u32 = 7;
+
+        let queue = Queue::new();
+        let b = Barrier::new(PRODUCERS as usize + 2);
+        let popped = thread::scope(|s| {
+            for p in 0..PRODUCERS {
+                let (queue, b) = (&queue, &b);
+                s.spawn(move || {
+                    b.wait();
+                    let values: Vec<_> = (0..ITER).map(|i| (p, i)).collect();
+                    for chunk in values.chunks(BATCH as usize) {
+                        queue.push_back_batch(chunk.iter().copied());
+                    }
+                });
+            }
+
+            let handles: Vec<_> = (0..2)
+                .map(|_| {
+                    s.spawn(|| {
+                        b.wait();
+                        let mut out = Vec::new();
+                        while out.len() < (PRODUCERS * ITER / 2) as usize {
+                            queue.pop_front_batch(5, &mut out);
+                        }
+                        out
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        // batches are popped by several threads, but each thread sees every
+        // producer's values in order
+        for out in &popped {
+            for p in 0..PRODUCERS {
+                let mine: Vec<_> = out.iter().filter(|(q, _)| *q == p).collect();
+                assert!(mine.windows(2).all(|w| w[0].1 < w[1].1));
+            }
+        }
+
+        let mut all: Vec<_> = popped.concat();
+        all.sort();
+        assert!(all
+            .into_iter()
+            .eq((0..PRODUCERS).flat_map(|p| (0..ITER).map(move |i| (p, i)))));
+        assert_eq!(queue.approximate_len(), 0);
+    }
+
+    #[test]
+    fn send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Queue<u8>>();
+        assert_send_sync::<Queue<std::sync::mpsc::Sender<u8>>>();
+    }
+}
+
+#[cfg(all(test, loom))]
+mod models {
+    use loom::thread;
+
+    use super::*;
+    use crate::reclaim::CheckedReclaimer;
+    use crate::testing::models::Checked;
+
+    type CheckedQueue = Queue<u32, CheckedReclaimer>;
+
+    // links a node after the current tail without advancing `tail`, as if the
+    // pushing thread had stalled right after its CAS on `next`
+    fn push_lagging(queue: &CheckedQueue, t: u32) {
+        let tail = queue.tail.load(Ordering::Acquire);
+        let new = queue.reclaimer.link_boxed(Node::new(t));
+        unsafe { &*new }.prev.store(tail, Ordering::Release);
+        unsafe { &*tail }.next.store(new, Ordering::Release);
+        queue.len.fetch_add(1, Ordering::Release);
+    }
+
+    #[test]
+    fn push_push() {
+        loom::model(|| {
+            let queue = CheckedQueue::with_values(&[]);
+            let other = thread::spawn({
+                let queue = queue.clone();
+                move || queue.push_back(1)
+            });
+            queue.push_back(2);
+            other.join().unwrap();
+
+            queue.push_back(3);
+            let mut popped = queue.drain();
+            assert_eq!(popped.pop(), Some(3));
+            popped.sort();
+            assert_eq!(popped, [1, 2]);
+        });
+    }
+
+    #[test]
+    fn push_pop() {
+        loom::model(|| {
+            let queue = CheckedQueue::with_values(&[]);
+            queue.push_back(1);
+            let other = thread::spawn({
+                let queue = queue.clone();
+                move || queue.push_back(2)
+            });
+            let first = queue.pop_front();
+            other.join().unwrap();
+
+            assert_eq!(first, Some(1));
+            assert_eq!(queue.drain(), [2]);
+        });
+    }
+
+    #[test]
+    fn pop_pop() {
+        loom::model(|| {
+            let queue = CheckedQueue::with_values(&[]);
+            queue.push_back(1);
+            queue.push_back(2);
+            let other = thread::spawn({
+                let queue = queue.clone();
+                move || queue.pop_front()
+            });
+            let mine = queue.pop_front();
+            let theirs = other.join().unwrap();
+
+            let mut popped = [mine.unwrap(), theirs.unwrap()];
+            popped.sort();
+            assert_eq!(popped, [1, 2]);
+            assert!(queue.is_empty());
+        });
+    }
+
+    // a push that finds `tail` lagging has to move it forward before it can
+    // link its own node
+    #[test]
+    fn push_helps_lagging_tail() {
+        loom::model(|| {
+            let queue = CheckedQueue::with_values(&[]);
+            push_lagging(&queue, 1);
+            let other = thread::spawn({
+                let queue = queue.clone();
+                move || queue.push_back(2)
+            });
+            queue.push_back(3);
+            other.join().unwrap();
+
+            let popped = queue.drain();
+            assert_eq!(popped[0], 1);
+            assert!(popped == [1, 2, 3] || popped == [1, 3, 2]);
+        });
+    }
+
+    // a pop that moves `head` past the node `tail` still points at has to
+    // move `tail` as well, or a racing push would link behind a retired node
+    #[test]
+    fn pop_fixes_lagging_tail() {
+        loom::model(|| {
+            let queue = CheckedQueue::with_values(&[]);
+            push_lagging(&queue, 1);
+            let other = thread::spawn({
+                let queue = queue.clone();
+                move || queue.push_back(2)
             });
+            let first = queue.pop_front();
+            other.join().unwrap();
+
+            assert_eq!(first, Some(1));
+            assert_eq!(queue.drain(), [2]);
+            queue.push_back(3);
+            assert_eq!(queue.drain(), [3]);
         });
-        assert_eq!(list.len(), 0);
     }
 }
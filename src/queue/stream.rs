@@ -0,0 +1,321 @@
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+
+use super::Queue;
+use crate::parking::Waiter;
+use crate::reclaim::{DefaultReclaimer, Reclaimer};
+
+impl<T, R: Reclaimer> Queue<T, R> {
+    /// Removes the front element, waiting until one is available.
+    pub fn pop_front_async(&self) -> PopFront<'_, T, R> {
+        PopFront {
+            queue: self,
+            waiter: None,
+        }
+    }
+}
+
+/// The future returned by [`Queue::pop_front_async`].
+pub struct PopFront<'a, T, R: Reclaimer = DefaultReclaimer> {
+    queue: &'a Queue<T, R>,
+    waiter: Option<Arc<Waiter>>,
+}
+
+impl<T, R: Reclaimer> Future for PopFront<'_, T, R> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let queue = this.queue;
+        queue
+            .consumers
+            .poll(cx, &mut this.waiter, || queue.pop_front())
+    }
+}
+
+impl<T, R: Reclaimer> Drop for PopFront<'_, T, R> {
+    fn drop(&mut self) {
+        if let Some(waiter) = self.waiter.take() {
+            self.queue.consumers.unregister(&waiter);
+        }
+    }
+}
+
+/// A [`Stream`] of the elements popped from the front of a shared queue.
+pub struct Consumer<T, R: Reclaimer = DefaultReclaimer> {
+    queue: Arc<Queue<T, R>>,
+    waiter: Option<Arc<Waiter>>,
+}
+
+impl<T, R: Reclaimer> Consumer<T, R> {
+    pub fn new(queue: Arc<Queue<T, R>>) -> Self {
+        Self {
+            queue,
+            waiter: None,
+        }
+    }
+
+    pub fn queue(&self) -> &Arc<Queue<T, R>> {
+        &self.queue
+    }
+}
+
+impl<T, R: Reclaimer> Stream for Consumer<T, R> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let queue = &*this.queue;
+        queue
+            .consumers
+            .poll(cx, &mut this.waiter, || queue.pop_front())
+            .map(Some)
+    }
+}
+
+impl<T, R: Reclaimer> Drop for Consumer<T, R> {
+    fn drop(&mut self) {
+        if let Some(waiter) = self.waiter.take() {
+            self.queue.consumers.unregister(&waiter);
+        }
+    }
+}
+
+/// A [`Sink`] pushing to the back of a shared queue.
+///
+/// On a bounded queue `poll_ready` reserves the slot the next element goes
+/// into, waiting for a consumer to make room if necessary.
+pub struct Producer<T, R: Reclaimer = DefaultReclaimer> {
+    queue: Arc<Queue<T, R>>,
+    waiter: Option<Arc<Waiter>>,
+    reserved: bool,
+}
+
+impl<T, R: Reclaimer> Producer<T, R> {
+    pub fn new(queue: Arc<Queue<T, R>>) -> Self {
+        Self {
+            queue,
+            waiter: None,
+            reserved: false,
+        }
+    }
+
+    pub fn queue(&self) -> &Arc<Queue<T, R>> {
+        &self.queue
+    }
+}
+
+impl<T, R: Reclaimer> Sink<T> for Producer<T, R> {
+    type Error = Infallible;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        if this.reserved {
+            return Poll::Ready(Ok(()));
+        }
+
+        let queue = &*this.queue;
+        let reserved = queue
+            .producers
+            .poll(cx, &mut this.waiter, || queue.try_reserve(1).then_some(()));
+        this.reserved = reserved.is_ready();
+        reserved.map(Ok)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        assert!(this.reserved, "start_send called without poll_ready");
+        this.reserved = false;
+        this.queue.link_back(item);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T, R: Reclaimer> Drop for Producer<T, R> {
+    fn drop(&mut self) {
+        if self.reserved {
+            self.queue.release();
+        }
+        if let Some(waiter) = self.waiter.take() {
+            self.queue.producers.unregister(&waiter);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::{poll_fn, Future};
+    use std::pin::pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Barrier;
+    use std::task::{Wake, Waker};
+    use std::thread::{self, Thread};
+
+    use super::*;
+    use crate::queue::Full;
+
+    #[derive(Default)]
+    struct CountWaker(AtomicUsize);
+
+    impl CountWaker {
+        fn wakes(&self) -> usize {
+            self.0.load(Ordering::Acquire)
+        }
+    }
+
+    impl Wake for CountWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::AcqRel);
+        }
+    }
+
+    fn counting() -> (Arc<CountWaker>, Waker) {
+        let count = Arc::new(CountWaker::default());
+        (count.clone(), Waker::from(count))
+    }
+
+    struct Unparker(Thread);
+
+    impl Wake for Unparker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let waker = Waker::from(Arc::new(Unparker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+
+    #[test]
+    fn pop_front_async() {
+        let queue = Queue::new();
+        let (count, waker) = counting();
+        let mut cx = Context::from_waker(&waker);
+
+        let mut pop = pin!(queue.pop_front_async());
+        assert_eq!(pop.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(count.wakes(), 0);
+
+        queue.push_back(1);
+        assert_eq!(count.wakes(), 1);
+        assert_eq!(pop.as_mut().poll(&mut cx), Poll::Ready(1));
+    }
+
+    #[test]
+    fn dropped_waiter_passes_notification_on() {
+        let queue = Queue::new();
+        let (first, first_waker) = counting();
+        let (second, second_waker) = counting();
+
+        let mut a = Box::pin(queue.pop_front_async());
+        let mut b = Box::pin(queue.pop_front_async());
+        assert!(a
+            .as_mut()
+            .poll(&mut Context::from_waker(&first_waker))
+            .is_pending());
+        assert!(b
+            .as_mut()
+            .poll(&mut Context::from_waker(&second_waker))
+            .is_pending());
+
+        queue.push_back(1);
+        assert_eq!((first.wakes(), second.wakes()), (1, 0));
+
+        drop(a);
+        assert_eq!(second.wakes(), 1);
+        let mut cx = Context::from_waker(&second_waker);
+        assert_eq!(b.as_mut().poll(&mut cx), Poll::Ready(1));
+    }
+
+    #[test]
+    fn sink_reserves_slot() {
+        let queue = Arc::new(Queue::with_capacity(1));
+        let mut producer = Producer::new(queue.clone());
+        let (count, waker) = counting();
+        let mut cx = Context::from_waker(&waker);
+
+        assert_eq!(
+            Pin::new(&mut producer).poll_ready(&mut cx),
+            Poll::Ready(Ok(()))
+        );
+        assert_eq!(queue.try_push_back(1), Err(Full(1)));
+        Pin::new(&mut producer).start_send(2).unwrap();
+
+        assert!(Pin::new(&mut producer).poll_ready(&mut cx).is_pending());
+        assert_eq!(queue.pop_front(), Some(2));
+        assert_eq!(count.wakes(), 1);
+
+        assert!(Pin::new(&mut producer).poll_ready(&mut cx).is_ready());
+        assert_eq!(queue.approximate_len(), 1);
+        drop(producer);
+        assert_eq!(queue.approximate_len(), 0);
+        assert_eq!(queue.try_push_back(3), Ok(()));
+    }
+
+    const ITER: usize = if cfg!(miri) { 20 } else { 1000 };
+    const THREADS: usize = 2;
+
+    #[test]
+    fn stream_sink_multi() {
+        let queue = Arc::new(Queue::with_capacity(4));
+        let b = Barrier::new(THREADS * 2);
+        let sum = thread::scope(|s| {
+            for t in 0..THREADS {
+                let (queue, b) = (queue.clone(), &b);
+                s.spawn(move || {
+                    let mut producer = Producer::new(queue);
+                    b.wait();
+                    for i in (t..ITER).step_by(THREADS) {
+                        block_on(poll_fn(|cx| Pin::new(&mut producer).poll_ready(cx))).unwrap();
+                        Pin::new(&mut producer).start_send(i).unwrap();
+                    }
+                });
+            }
+
+            let handles: Vec<_> = (0..THREADS)
+                .map(|_| {
+                    let (queue, b) = (queue.clone(), &b);
+                    s.spawn(move || {
+                        let mut consumer = Consumer::new(queue);
+                        b.wait();
+                        (0..ITER / THREADS)
+                            .map(|_| {
+                                block_on(poll_fn(|cx| Pin::new(&mut consumer).poll_next(cx)))
+                                    .unwrap()
+                            })
+                            .sum::<usize>()
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap())
+                .sum::<usize>()
+        });
+        assert_eq!(sum, (0..ITER).sum());
+        assert!(queue.is_empty());
+    }
+}
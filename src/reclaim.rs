@@ -0,0 +1,665 @@
+//! Memory reclamation backends for the containers.
+//!
+//! Every container is generic over a [`Reclaimer`], defaulting to
+//! [`SeizeReclaimer`]. The others are there to compare latency and memory
+//! overhead on a given workload:
+//!
+//! ```
+//! use wal::reclaim::EpochReclaimer;
+//! use wal::queue::Queue;
+//!
+//! let queue = Queue::with_reclaimer(EpochReclaimer::new());
+//! queue.push_back(1);
+//! assert_eq!(queue.pop_front(), Some(1));
+//! ```
+//!
+//! Containers built on the same seize [`Collector`] share one reclamation
+//! domain, so a single guard covers operations on all of them:
+//!
+//! ```
+//! use std::sync::Arc;
+//! use wal::reclaim::Collector;
+//! use wal::queue::Queue;
+//!
+//! let collector = Arc::new(Collector::new());
+//! let (a, b) = (
+//!     Queue::with_collector(collector.clone()),
+//!     Queue::with_collector(collector.clone()),
+//! );
+//!
+//! let guard = collector.enter();
+//! a.push_back_with(1, &guard);
+//! b.push_back_with(a.pop_front_with(&guard).unwrap(), &guard);
+//! assert_eq!(b.front(&guard), Some(1));
+//! ```
+
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use seize::reclaim;
+pub use seize::{Collector, Guard};
+
+use crate::sync::{AtomicPtr, Ordering};
+
+#[cfg(feature = "allocator-api2")]
+mod alloc;
+#[cfg(test)]
+mod checked;
+mod epoch;
+mod hazard;
+mod pool;
+
+#[cfg(feature = "allocator-api2")]
+pub use alloc::AllocReclaimer;
+#[cfg(feature = "allocator-api2")]
+pub use allocator_api2::alloc::Allocator;
+#[cfg(test)]
+pub(crate) use checked::CheckedReclaimer;
+pub use epoch::{EpochGuard, EpochReclaimer};
+pub use hazard::{HazardEraGuard, HazardEraReclaimer};
+
+/// The reclaimer used when none is named.
+pub type DefaultReclaimer = SeizeReclaimer;
+
+/// A scheme deciding when nodes removed from a container can be freed.
+///
+/// # Safety
+///
+/// A node handed to [`Reclaimer::retire`] must stay allocated until every
+/// guard that was entered before it was retired has been dropped.
+///
+/// The containers follow links out of nodes that were unlinked after they
+/// were loaded, so protecting only the addresses that were explicitly
+/// loaded, as classic hazard pointers do, is not enough.
+pub unsafe trait Reclaimer: Send + Sync {
+    /// A node together with whatever header the scheme keeps for it.
+    type Linked<T>: Deref<Target = T> + DerefMut;
+
+    /// Keeps nodes retired after it was entered from being freed.
+    type Guard<'a>
+    where
+        Self: 'a;
+
+    fn enter(&self) -> Self::Guard<'_>;
+
+    /// Shortens the lifetime of a guard, which the compiler cannot do on its
+    /// own through the associated type.
+    fn reborrow<'a>(guard: &'a Self::Guard<'_>) -> &'a Self::Guard<'a>;
+
+    /// Whether `guard` was entered on this reclaimer.
+    fn owns(&self, guard: &Self::Guard<'_>) -> bool;
+
+    /// Allocates a node.
+    fn link_boxed<T>(&self, value: T) -> *mut Self::Linked<T>;
+
+    /// Loads a pointer to a node, with at least `Acquire` ordering.
+    fn protect<T>(
+        guard: &Self::Guard<'_>,
+        ptr: &AtomicPtr<Self::Linked<T>>,
+        ordering: Ordering,
+    ) -> *mut Self::Linked<T>;
+
+    /// Frees a node once no guard can still be reading it.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from [`Reclaimer::link_boxed`] on this reclaimer, be
+    /// unreachable for guards entered from now on and be retired only once.
+    unsafe fn retire<T>(&self, ptr: *mut Self::Linked<T>);
+
+    /// Frees a node right away.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from [`Reclaimer::link_boxed`] on this reclaimer and
+    /// no other thread may be able to reach it.
+    unsafe fn free<T>(&self, ptr: *mut Self::Linked<T>);
+}
+
+#[inline]
+fn acquire(ordering: Ordering) -> Ordering {
+    match ordering {
+        Ordering::SeqCst => Ordering::SeqCst,
+        _ => Ordering::Acquire,
+    }
+}
+
+/// Reclamation through a [`Collector`].
+///
+/// The collector can be shared by any number of containers, which then
+/// accept each other's guards. Cloning the reclaimer shares its collector.
+///
+/// Under Miri, collectors created here or by a
+/// [`Builder`](crate::builder::Builder) free retired nodes as soon as seize
+/// allows instead of in batches, so that a node read after it should have
+/// been freed is caught within the few operations a test runs there.
+#[derive(Debug, Clone)]
+pub struct SeizeReclaimer {
+    collector: Arc<Collector>,
+    recycle: bool,
+}
+
+impl Default for SeizeReclaimer {
+    fn default() -> Self {
+        Self::shared(Arc::new(collector()))
+    }
+}
+
+/// A collector with seize's defaults, other than under Miri.
+pub(crate) fn collector() -> Collector {
+    let collector = Collector::new();
+    #[cfg(miri)]
+    let collector = collector.batch_size(1);
+    collector
+}
+
+impl SeizeReclaimer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a reclaimer that retires nodes into `collector`.
+    pub fn shared(collector: Arc<Collector>) -> Self {
+        Self {
+            collector,
+            recycle: false,
+        }
+    }
+
+    /// Keeps reclaimed nodes in a free list of the thread that reclaimed
+    /// them, for the next node that thread allocates, instead of returning
+    /// them to the global allocator.
+    ///
+    /// Each thread holds at most 256 free nodes of a given size. Recycling
+    /// pays off when the threads that pop also push, since nodes are
+    /// reclaimed by whichever thread happens to flush seize's batch.
+    pub fn recycle(mut self, recycle: bool) -> Self {
+        self.recycle = recycle;
+        self
+    }
+
+    pub fn collector(&self) -> &Arc<Collector> {
+        &self.collector
+    }
+
+    /// Fills the current thread's free list with up to `n` nodes holding a
+    /// `T`, if this reclaimer recycles.
+    pub(crate) fn preallocate<T>(&self, n: usize) {
+        if self.recycle {
+            pool::reserve::<seize::Linked<T>>(n);
+        }
+    }
+}
+
+unsafe fn recycle<T>(mut link: seize::Link) {
+    pool::put(link.cast::<T>());
+}
+
+unsafe impl Reclaimer for SeizeReclaimer {
+    type Linked<T> = seize::Linked<T>;
+    type Guard<'a> = Guard<'a>;
+
+    #[inline]
+    fn enter(&self) -> Guard<'_> {
+        self.collector.enter()
+    }
+
+    #[inline]
+    fn reborrow<'a>(guard: &'a Guard<'_>) -> &'a Guard<'a> {
+        guard
+    }
+
+    #[inline]
+    fn owns(&self, guard: &Guard<'_>) -> bool {
+        guard
+            .collector()
+            .is_some_and(|other| Collector::ptr_eq(other, &self.collector))
+    }
+
+    #[inline]
+    fn link_boxed<T>(&self, value: T) -> *mut seize::Linked<T> {
+        if self.recycle {
+            if let Some(ptr) = pool::take::<seize::Linked<T>>() {
+                unsafe { ptr.as_ptr().write(self.collector.link(value)) };
+                return ptr.as_ptr();
+            }
+        }
+        self.collector.link_boxed(value)
+    }
+
+    #[inline]
+    fn protect<T>(
+        guard: &Guard<'_>,
+        ptr: &AtomicPtr<seize::Linked<T>>,
+        ordering: Ordering,
+    ) -> *mut seize::Linked<T> {
+        // seize's own state is not modeled, so models use other reclaimers
+        #[cfg(loom)]
+        return {
+            let _ = guard;
+            ptr.load(acquire(ordering))
+        };
+        #[cfg(not(loom))]
+        guard.protect(ptr, acquire(ordering))
+    }
+
+    #[inline]
+    unsafe fn retire<T>(&self, ptr: *mut seize::Linked<T>) {
+        if self.recycle {
+            self.collector.retire(ptr, recycle::<T>);
+        } else {
+            self.collector.retire(ptr, reclaim::boxed::<T>);
+        }
+    }
+
+    #[inline]
+    unsafe fn free<T>(&self, ptr: *mut seize::Linked<T>) {
+        if self.recycle {
+            pool::put(ptr);
+        } else {
+            drop(Box::from_raw(ptr));
+        }
+    }
+}
+
+/// Never frees retired nodes, for measuring what reclamation costs.
+///
+/// Values are still dropped when popped or when the container is dropped,
+/// only the nodes themselves leak.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LeakReclaimer;
+
+impl LeakReclaimer {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+unsafe impl Reclaimer for LeakReclaimer {
+    type Linked<T> = Boxed<T>;
+    type Guard<'a> = ();
+
+    #[inline]
+    fn enter(&self) {}
+
+    #[inline]
+    fn reborrow(guard: &()) -> &() {
+        guard
+    }
+
+    #[inline]
+    fn owns(&self, _guard: &()) -> bool {
+        true
+    }
+
+    #[inline]
+    fn link_boxed<T>(&self, value: T) -> *mut Boxed<T> {
+        Box::into_raw(Box::new(Boxed(value)))
+    }
+
+    #[inline]
+    fn protect<T>(_guard: &(), ptr: &AtomicPtr<Boxed<T>>, ordering: Ordering) -> *mut Boxed<T> {
+        ptr.load(acquire(ordering))
+    }
+
+    #[inline]
+    unsafe fn retire<T>(&self, _ptr: *mut Boxed<T>) {}
+
+    #[inline]
+    unsafe fn free<T>(&self, ptr: *mut Boxed<T>) {
+        drop(Box::from_raw(ptr));
+    }
+}
+
+/// A node of a reclaimer that keeps no per-node header.
+#[derive(Debug)]
+#[repr(transparent)]
+pub struct Boxed<T>(T);
+
+impl<T> Deref for Boxed<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Boxed<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+// A node waiting for its reclaimer to decide it can be freed.
+struct Retired {
+    ptr: *mut u8,
+    free: unsafe fn(*mut u8),
+    // when it was retired, and for `HazardEraReclaimer` when it was allocated
+    retired: u64,
+    birth: u64,
+}
+
+unsafe impl Send for Retired {}
+
+impl Retired {
+    fn new<T>(ptr: *mut T, retired: u64, birth: u64) -> Self {
+        unsafe fn free<T>(ptr: *mut u8) {
+            drop(Box::from_raw(ptr.cast::<T>()));
+        }
+
+        Self {
+            ptr: ptr.cast(),
+            free: free::<T>,
+            retired,
+            birth,
+        }
+    }
+
+    unsafe fn free(self) {
+        (self.free)(self.ptr)
+    }
+}
+
+// An append-only list of per-guard state, reused once a guard is dropped.
+struct Slots<S> {
+    head: AtomicPtr<Slot<S>>,
+}
+
+struct Slot<S> {
+    state: S,
+    active: AtomicBool,
+    next: *mut Slot<S>,
+}
+
+impl<S: Default> Slots<S> {
+    fn new() -> Self {
+        Self {
+            head: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    fn acquire(&self) -> &Slot<S> {
+        let mut current = self.head.load(Ordering::Acquire);
+        while !current.is_null() {
+            let slot = unsafe { &*current };
+            if !slot.active.load(Ordering::Relaxed)
+                && slot
+                    .active
+                    .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            {
+                return slot;
+            }
+            current = slot.next;
+        }
+
+        let new = Box::into_raw(Box::new(Slot {
+            state: S::default(),
+            active: AtomicBool::new(true),
+            next: ptr::null_mut(),
+        }));
+
+        loop {
+            let head = self.head.load(Ordering::Acquire);
+            unsafe { (*new).next = head };
+            if self
+                .head
+                .compare_exchange(head, new, Ordering::Release, Ordering::Relaxed)
+                .is_ok()
+            {
+                return unsafe { &*new };
+            }
+        }
+    }
+
+    fn release(&self, slot: &Slot<S>) {
+        slot.active.store(false, Ordering::Release);
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &S> {
+        let mut current = self.head.load(Ordering::Acquire);
+        std::iter::from_fn(move || {
+            let slot = unsafe { current.as_ref()? };
+            current = slot.next;
+            Some(&slot.state)
+        })
+    }
+}
+
+impl<S> Drop for Slots<S> {
+    fn drop(&mut self) {
+        let mut current = self.head.load(Ordering::Relaxed);
+        while !current.is_null() {
+            let slot = unsafe { Box::from_raw(current) };
+            current = slot.next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Barrier;
+    use std::thread;
+
+    use super::*;
+    use crate::testing::DropCount;
+    use crate::{doubly, queue::Queue, LinkedList};
+
+    fn retire_waits_for_guards<R: Reclaimer + Default>() {
+        let dropped = AtomicUsize::new(0);
+        let reclaimer = R::default();
+        // publishes each node before retiring it, so `guard` may have read it
+        let retire_some = |guard: &R::Guard<'_>| {
+            for _ in 0..256 {
+                let link = AtomicPtr::new(reclaimer.link_boxed(DropCount(&dropped)));
+                let node = R::protect(guard, &link, Ordering::Acquire);
+                unsafe { reclaimer.retire(node) };
+            }
+        };
+
+        let guard = reclaimer.enter();
+        assert!(reclaimer.owns(&guard));
+        retire_some(&guard);
+        assert_eq!(dropped.load(Ordering::Relaxed), 0);
+
+        drop(guard);
+        retire_some(&reclaimer.enter());
+        assert!(dropped.load(Ordering::Relaxed) > 0);
+
+        drop(reclaimer);
+        assert_eq!(dropped.load(Ordering::Relaxed), 512);
+    }
+
+    #[test]
+    fn epoch_retire() {
+        retire_waits_for_guards::<EpochReclaimer>();
+    }
+
+    #[test]
+    fn hazard_era_retire() {
+        retire_waits_for_guards::<HazardEraReclaimer>();
+    }
+
+    #[test]
+    fn recycle() {
+        let collector = Arc::new(Collector::new().batch_size(1));
+        let reclaimer = SeizeReclaimer::shared(collector).recycle(true);
+
+        let dropped = AtomicUsize::new(0);
+        let a = reclaimer.link_boxed(DropCount(&dropped));
+        unsafe { reclaimer.retire(a) };
+        assert_eq!(dropped.load(Ordering::Relaxed), 1);
+
+        let b = reclaimer.link_boxed(DropCount(&dropped));
+        assert_eq!(a, b);
+        unsafe { reclaimer.free(b) };
+        assert_eq!(dropped.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn foreign_guard() {
+        let (a, b) = (EpochReclaimer::new(), EpochReclaimer::new());
+        assert!(!a.owns(&b.enter()));
+        let (a, b) = (HazardEraReclaimer::new(), HazardEraReclaimer::new());
+        assert!(!a.owns(&b.enter()));
+    }
+
+    const THREADS: usize = 4;
+    const ITER: usize = if cfg!(miri) { 20 } else { 1000 };
+
+    // Pushes and pops from every thread at once, returning the sum of what
+    // was popped and what was left behind.
+    fn push_pop<C: Sync>(
+        container: &C,
+        push: impl Fn(&C, usize) + Sync,
+        pop: impl Fn(&C) -> Option<usize> + Sync,
+    ) -> usize {
+        let b = Barrier::new(THREADS);
+        let popped: usize = thread::scope(|s| {
+            let handles: Vec<_> = (0..THREADS)
+                .map(|t| {
+                    let (b, push, pop) = (&b, &push, &pop);
+                    s.spawn(move || {
+                        b.wait();
+                        let mut sum = 0;
+                        for i in (t..ITER).step_by(THREADS) {
+                            push(container, i);
+                            sum += pop(container).unwrap_or(0);
+                        }
+                        sum
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).sum()
+        });
+
+        popped + std::iter::from_fn(|| pop(container)).sum::<usize>()
+    }
+
+    fn containers<R: Reclaimer>(new: impl Fn() -> R) {
+        let sum = (0..ITER).sum();
+
+        let list = LinkedList::with_reclaimer(new());
+        assert_eq!(
+            push_pop(&list, LinkedList::push_back, LinkedList::pop_front),
+            sum
+        );
+        assert_eq!(
+            push_pop(&list, LinkedList::push_front, LinkedList::pop_back),
+            sum
+        );
+
+        let list = doubly::LinkedList::with_reclaimer(new());
+        assert_eq!(
+            push_pop(
+                &list,
+                doubly::LinkedList::push_back,
+                doubly::LinkedList::pop_front
+            ),
+            sum
+        );
+        assert_eq!(
+            push_pop(
+                &list,
+                doubly::LinkedList::push_front,
+                doubly::LinkedList::pop_back
+            ),
+            sum
+        );
+
+        let queue = Queue::with_reclaimer(new());
+        assert_eq!(push_pop(&queue, Queue::push_back, Queue::pop_front), sum);
+        let guard = queue.guard();
+        assert_eq!(queue.iter(&guard).count(), 0);
+    }
+
+    #[test]
+    fn seize_containers() {
+        containers(SeizeReclaimer::new);
+    }
+
+    #[test]
+    fn seize_recycle_containers() {
+        containers(|| SeizeReclaimer::new().recycle(true));
+    }
+
+    #[test]
+    fn seize_preallocate() {
+        // on a thread of its own, so that its free list starts out empty
+        thread::spawn(|| {
+            let reclaimer = SeizeReclaimer::new().recycle(true);
+            reclaimer.preallocate::<u64>(3);
+            let nodes = [reclaimer.link_boxed(1_u64), reclaimer.link_boxed(2)];
+
+            // the two nodes came out of the three preallocated ones
+            let left = pool::take::<seize::Linked<u64>>().unwrap();
+            assert!(pool::take::<seize::Linked<u64>>().is_none());
+            unsafe {
+                std::alloc::dealloc(
+                    left.as_ptr().cast(),
+                    std::alloc::Layout::new::<seize::Linked<u64>>(),
+                );
+                for node in nodes {
+                    reclaimer.free(node);
+                }
+            }
+        })
+        .join()
+        .unwrap();
+    }
+
+    #[test]
+    fn epoch_containers() {
+        containers(EpochReclaimer::new);
+    }
+
+    #[test]
+    fn hazard_era_containers() {
+        containers(HazardEraReclaimer::new);
+    }
+
+    #[test]
+    fn checked_containers() {
+        containers(CheckedReclaimer::new);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore = "leaks nodes by design, which miri reports")]
+    fn leak_containers() {
+        containers(LeakReclaimer::new);
+    }
+
+    #[cfg(feature = "allocator-api2")]
+    #[test]
+    fn alloc_containers() {
+        use allocator_api2::alloc::{AllocError, Global, Layout};
+        use std::ptr::NonNull;
+
+        #[derive(Default)]
+        struct Counting {
+            allocated: AtomicUsize,
+            live: AtomicUsize,
+        }
+
+        unsafe impl Allocator for Counting {
+            fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+                self.allocated.fetch_add(1, Ordering::Relaxed);
+                self.live.fetch_add(1, Ordering::Relaxed);
+                Global.allocate(layout)
+            }
+
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                self.live.fetch_sub(1, Ordering::Relaxed);
+                Global.deallocate(ptr, layout)
+            }
+        }
+
+        let alloc = Counting::default();
+        containers(|| AllocReclaimer::new_in(&alloc));
+        assert!(alloc.allocated.load(Ordering::Relaxed) >= 5 * ITER);
+        assert_eq!(alloc.live.load(Ordering::Relaxed), 0);
+    }
+}
@@ -0,0 +1,191 @@
+use std::alloc::{handle_alloc_error, Layout};
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+use std::sync::Arc;
+use std::{fmt, ptr};
+
+use allocator_api2::alloc::Allocator;
+use seize::{Collector, Guard};
+
+use super::{acquire, Reclaimer};
+use crate::sync::{AtomicPtr, Ordering};
+
+/// Reclamation through a [`Collector`], of nodes allocated in `A`.
+///
+/// Every node remembers the allocator it came from, so the collector frees
+/// it back into that allocator from whichever thread reclaims it. Cloning
+/// the reclaimer shares both the collector and the allocator, which live
+/// until the last clone and every node retired into them are gone.
+///
+/// ```
+/// use allocator_api2::alloc::Global;
+/// use wal::queue::Queue;
+/// use wal::reclaim::AllocReclaimer;
+///
+/// let queue = Queue::with_reclaimer(AllocReclaimer::new_in(Global));
+/// queue.push_back(1);
+/// assert_eq!(queue.pop_front(), Some(1));
+/// ```
+pub struct AllocReclaimer<A: Allocator> {
+    // Dropped first, so the clone that drops the collector last reclaims
+    // whatever is still retired into it while still holding the allocator.
+    // The allocator has an `Arc` of its own for nodes to point into, which
+    // dropping the collector leaves alone.
+    collector: Arc<Collector>,
+    alloc: Arc<A>,
+}
+
+impl<A: Allocator> AllocReclaimer<A> {
+    pub fn new_in(alloc: A) -> Self {
+        Self::with_collector_in(super::collector(), alloc)
+    }
+
+    /// Creates a reclaimer that retires nodes allocated in `alloc` into
+    /// `collector`, which can be tuned beforehand.
+    pub fn with_collector_in(collector: Collector, alloc: A) -> Self {
+        Self {
+            collector: Arc::new(collector),
+            alloc: Arc::new(alloc),
+        }
+    }
+
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+}
+
+impl<A: Allocator> Clone for AllocReclaimer<A> {
+    fn clone(&self) -> Self {
+        Self {
+            collector: self.collector.clone(),
+            alloc: self.alloc.clone(),
+        }
+    }
+}
+
+impl<A: Allocator + fmt::Debug> fmt::Debug for AllocReclaimer<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AllocReclaimer")
+            .field("collector", &self.collector)
+            .field("alloc", &self.alloc)
+            .finish()
+    }
+}
+
+/// A node of an [`AllocReclaimer`].
+#[repr(transparent)]
+pub struct Linked<T, A>(seize::Linked<Allocated<T, A>>);
+
+struct Allocated<T, A> {
+    alloc: *const A,
+    value: T,
+}
+
+impl<T, A> Deref for Linked<T, A> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0.value
+    }
+}
+
+impl<T, A> DerefMut for Linked<T, A> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0.value
+    }
+}
+
+impl<T: fmt::Debug, A> fmt::Debug for Linked<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.value.fmt(f)
+    }
+}
+
+// Drops the node at `ptr` and frees it into the allocator it came from.
+unsafe fn free<T, A: Allocator>(ptr: *mut Linked<T, A>) {
+    let node = &*ptr;
+    let alloc = &*node.0.alloc;
+    ptr::drop_in_place(ptr);
+    alloc.deallocate(
+        NonNull::new_unchecked(ptr).cast(),
+        Layout::new::<Linked<T, A>>(),
+    );
+}
+
+unsafe fn reclaim<T, A: Allocator>(mut link: seize::Link) {
+    free::<T, A>(link.cast::<Allocated<T, A>>().cast());
+}
+
+unsafe impl<A: Allocator + Send + Sync> Reclaimer for AllocReclaimer<A> {
+    type Linked<T> = Linked<T, A>;
+    type Guard<'a>
+        = Guard<'a>
+    where
+        A: 'a;
+
+    #[inline]
+    fn enter(&self) -> Guard<'_> {
+        self.collector.enter()
+    }
+
+    #[inline]
+    fn reborrow<'a>(guard: &'a Guard<'_>) -> &'a Guard<'a> {
+        guard
+    }
+
+    #[inline]
+    fn owns(&self, guard: &Guard<'_>) -> bool {
+        guard
+            .collector()
+            .is_some_and(|other| Collector::ptr_eq(other, &self.collector))
+    }
+
+    #[inline]
+    fn link_boxed<T>(&self, value: T) -> *mut Linked<T, A> {
+        let layout = Layout::new::<Linked<T, A>>();
+        let ptr = match self.alloc.allocate(layout) {
+            Ok(ptr) => ptr.cast::<Linked<T, A>>().as_ptr(),
+            Err(_) => handle_alloc_error(layout),
+        };
+
+        let alloc = Arc::as_ptr(&self.alloc);
+        let linked = self.collector.link(Allocated { alloc, value });
+        unsafe { ptr.write(Linked(linked)) };
+        ptr
+    }
+
+    #[inline]
+    fn protect<T>(
+        guard: &Guard<'_>,
+        ptr: &AtomicPtr<Linked<T, A>>,
+        ordering: Ordering,
+    ) -> *mut Linked<T, A> {
+        #[cfg(loom)]
+        return {
+            let _ = guard;
+            ptr.load(acquire(ordering))
+        };
+
+        // `Linked` is transparent over seize's node
+        #[cfg(not(loom))]
+        {
+            let ptr = unsafe { &*(ptr as *const AtomicPtr<Linked<T, A>>).cast() };
+            guard
+                .protect::<Allocated<T, A>>(ptr, acquire(ordering))
+                .cast()
+        }
+    }
+
+    #[inline]
+    unsafe fn retire<T>(&self, ptr: *mut Linked<T, A>) {
+        self.collector.retire(
+            ptr.cast::<seize::Linked<Allocated<T, A>>>(),
+            reclaim::<T, A>,
+        );
+    }
+
+    #[inline]
+    unsafe fn free<T>(&self, ptr: *mut Linked<T, A>) {
+        free(ptr);
+    }
+}
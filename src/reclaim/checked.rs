@@ -0,0 +1,128 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+
+use crate::sync::{AtomicPtr, AtomicUsize, Ordering};
+
+use super::{acquire, Reclaimer};
+
+/// Frees nodes only when dropped, but panics when one is loaded through a
+/// guard entered after the node was retired, which the contract of
+/// [`Reclaimer::retire`] rules out. Used to catch containers breaking it in
+/// tests and models.
+#[derive(Debug, Default)]
+pub(crate) struct CheckedReclaimer {
+    clock: AtomicUsize,
+    retired: Mutex<Vec<Retired>>,
+}
+
+#[derive(Debug)]
+struct Retired {
+    ptr: *mut u8,
+    free: unsafe fn(*mut u8),
+}
+
+// only touched again when the reclaimer is dropped
+unsafe impl Send for Retired {}
+
+unsafe fn free<T>(ptr: *mut u8) {
+    drop(Box::from_raw(ptr.cast::<Linked<T>>()));
+}
+
+impl CheckedReclaimer {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+}
+
+pub(crate) struct CheckedGuard<'a> {
+    reclaimer: &'a CheckedReclaimer,
+    entered: usize,
+}
+
+#[derive(Debug)]
+pub(crate) struct Linked<T> {
+    // the tick of the clock the node was retired at, zero while it is live
+    retired: AtomicUsize,
+    value: T,
+}
+
+impl<T> Deref for Linked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Linked<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+unsafe impl Reclaimer for CheckedReclaimer {
+    type Linked<T> = Linked<T>;
+    type Guard<'a> = CheckedGuard<'a>;
+
+    fn enter(&self) -> CheckedGuard<'_> {
+        CheckedGuard {
+            reclaimer: self,
+            entered: self.clock.load(Ordering::SeqCst),
+        }
+    }
+
+    fn reborrow<'a>(guard: &'a CheckedGuard<'_>) -> &'a CheckedGuard<'a> {
+        guard
+    }
+
+    fn owns(&self, guard: &CheckedGuard<'_>) -> bool {
+        std::ptr::eq(guard.reclaimer, self)
+    }
+
+    fn link_boxed<T>(&self, value: T) -> *mut Linked<T> {
+        Box::into_raw(Box::new(Linked {
+            retired: AtomicUsize::new(0),
+            value,
+        }))
+    }
+
+    fn protect<T>(
+        guard: &CheckedGuard<'_>,
+        ptr: &AtomicPtr<Linked<T>>,
+        ordering: Ordering,
+    ) -> *mut Linked<T> {
+        let loaded = ptr.load(acquire(ordering));
+
+        // the singly linked list tags the low bit of its links
+        let node = loaded.map_addr(|addr| addr & !1);
+        if !node.is_null() {
+            let retired = unsafe { &*node }.retired.load(Ordering::SeqCst);
+            assert!(
+                retired == 0 || retired > guard.entered,
+                "loaded a node retired before the guard was entered"
+            );
+        }
+        loaded
+    }
+
+    unsafe fn retire<T>(&self, ptr: *mut Linked<T>) {
+        let tick = self.clock.fetch_add(1, Ordering::SeqCst) + 1;
+        (*ptr).retired.store(tick, Ordering::SeqCst);
+        self.retired.lock().unwrap().push(Retired {
+            ptr: ptr.cast(),
+            free: free::<T>,
+        });
+    }
+
+    unsafe fn free<T>(&self, ptr: *mut Linked<T>) {
+        free::<T>(ptr.cast());
+    }
+}
+
+impl Drop for CheckedReclaimer {
+    fn drop(&mut self) {
+        for retired in self.retired.get_mut().unwrap().drain(..) {
+            unsafe { (retired.free)(retired.ptr) };
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::mem;
+use std::sync::atomic::{self, AtomicU64};
+use std::sync::Mutex;
+
+use crate::sync::{AtomicPtr, Ordering};
+
+use super::{acquire, Boxed, Reclaimer, Retired, Slot, Slots};
+
+// Retired nodes are only looked at once this many have piled up.
+const COLLECT_AFTER: usize = 64;
+
+// A guard's slot holds the epoch it was entered in, shifted left, with the
+// low bit set while the guard is alive.
+const PINNED: u64 = 1;
+
+/// Classic epoch-based reclamation.
+///
+/// A node retired in epoch `e` is freed once the global epoch reaches
+/// `e + 2`, which it can only do after every guard entered in `e` or
+/// earlier is dropped. A single long-lived guard therefore holds back
+/// everything retired after it was entered.
+pub struct EpochReclaimer {
+    epoch: AtomicU64,
+    slots: Slots<AtomicU64>,
+    retired: Mutex<Vec<Retired>>,
+}
+
+impl EpochReclaimer {
+    pub fn new() -> Self {
+        Self {
+            epoch: AtomicU64::new(0),
+            slots: Slots::new(),
+            retired: Mutex::new(Vec::new()),
+        }
+    }
+
+    // Moves the global epoch forward if every live guard has seen the
+    // current one, returning the epoch afterwards.
+    fn try_advance(&self) -> u64 {
+        let epoch = self.epoch.load(Ordering::Relaxed);
+        atomic::fence(Ordering::SeqCst);
+
+        for slot in self.slots.iter() {
+            let state = slot.load(Ordering::Relaxed);
+            if state & PINNED != 0 && state >> 1 != epoch {
+                return epoch;
+            }
+        }
+
+        atomic::fence(Ordering::Acquire);
+        self.epoch.store(epoch + 1, Ordering::Release);
+        epoch + 1
+    }
+}
+
+impl Default for EpochReclaimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A guard entered on an [`EpochReclaimer`].
+pub struct EpochGuard<'a> {
+    reclaimer: &'a EpochReclaimer,
+    slot: &'a Slot<AtomicU64>,
+}
+
+impl Drop for EpochGuard<'_> {
+    fn drop(&mut self) {
+        self.slot.state.store(0, Ordering::Release);
+        self.reclaimer.slots.release(self.slot);
+    }
+}
+
+unsafe impl Reclaimer for EpochReclaimer {
+    type Linked<T> = Boxed<T>;
+    type Guard<'a> = EpochGuard<'a>;
+
+    fn enter(&self) -> EpochGuard<'_> {
+        let slot = self.slots.acquire();
+        let epoch = self.epoch.load(Ordering::Relaxed);
+        slot.state.store(epoch << 1 | PINNED, Ordering::Relaxed);
+        // the pin has to be visible before anything is loaded under it
+        atomic::fence(Ordering::SeqCst);
+
+        EpochGuard {
+            reclaimer: self,
+            slot,
+        }
+    }
+
+    #[inline]
+    fn reborrow<'a>(guard: &'a EpochGuard<'_>) -> &'a EpochGuard<'a> {
+        guard
+    }
+
+    #[inline]
+    fn owns(&self, guard: &EpochGuard<'_>) -> bool {
+        std::ptr::eq(guard.reclaimer, self)
+    }
+
+    #[inline]
+    fn link_boxed<T>(&self, value: T) -> *mut Boxed<T> {
+        Box::into_raw(Box::new(Boxed(value)))
+    }
+
+    #[inline]
+    fn protect<T>(
+        _guard: &EpochGuard<'_>,
+        ptr: &AtomicPtr<Boxed<T>>,
+        ordering: Ordering,
+    ) -> *mut Boxed<T> {
+        ptr.load(acquire(ordering))
+    }
+
+    unsafe fn retire<T>(&self, ptr: *mut Boxed<T>) {
+        // pairs with the fence in `enter`, a guard pinned in a later epoch
+        // than the one we read cannot have seen the node linked
+        atomic::fence(Ordering::SeqCst);
+        let epoch = self.epoch.load(Ordering::Relaxed);
+
+        let mut retired = self.retired.lock().unwrap();
+        retired.push(Retired::new(ptr, epoch, 0));
+        if retired.len() < COLLECT_AFTER {
+            return;
+        }
+
+        let epoch = self.try_advance();
+        let (ready, pending) = mem::take(&mut *retired)
+            .into_iter()
+            .partition::<Vec<_>, _>(|node| node.retired + 2 <= epoch);
+        *retired = pending;
+        drop(retired);
+
+        for node in ready {
+            node.free();
+        }
+    }
+
+    #[inline]
+    unsafe fn free<T>(&self, ptr: *mut Boxed<T>) {
+        drop(Box::from_raw(ptr));
+    }
+}
+
+impl Drop for EpochReclaimer {
+    fn drop(&mut self) {
+        for node in self.retired.get_mut().unwrap().drain(..) {
+            unsafe { node.free() };
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{self, AtomicU64};
+use std::sync::Mutex;
+
+use crate::sync::{AtomicPtr, Ordering};
+
+use super::{Reclaimer, Retired, Slot, Slots};
+
+// Retired nodes are only looked at once this many have piled up.
+const COLLECT_AFTER: usize = 64;
+
+// The era moves forward once every this many allocations.
+const ERA_FREQUENCY: u64 = 32;
+
+// The reservation of a slot without a live guard, which overlaps no node.
+const NONE: u64 = u64::MAX;
+
+/// Hazard eras, in the interval form that the containers need.
+///
+/// Rather than publishing the address of every node it reads, as hazard
+/// pointers do, a guard publishes the range of eras it has read in. Nodes
+/// are stamped with the era they were allocated and retired in, and one is
+/// freed once its lifetime overlaps no live guard's range.
+///
+/// Unlike [`EpochReclaimer`](super::EpochReclaimer), a stalled guard only
+/// holds back the nodes that were allocated before it last read a pointer.
+pub struct HazardEraReclaimer {
+    era: AtomicU64,
+    allocated: AtomicU64,
+    slots: Slots<Reservation>,
+    retired: Mutex<Vec<Retired>>,
+}
+
+struct Reservation {
+    lower: AtomicU64,
+    upper: AtomicU64,
+}
+
+impl Default for Reservation {
+    fn default() -> Self {
+        Self {
+            lower: AtomicU64::new(NONE),
+            upper: AtomicU64::new(NONE),
+        }
+    }
+}
+
+impl HazardEraReclaimer {
+    pub fn new() -> Self {
+        Self {
+            era: AtomicU64::new(0),
+            allocated: AtomicU64::new(0),
+            slots: Slots::new(),
+            retired: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn is_reserved(&self, node: &Retired) -> bool {
+        self.slots.iter().any(|reservation| {
+            node.birth <= reservation.upper.load(Ordering::Relaxed)
+                && node.retired >= reservation.lower.load(Ordering::Relaxed)
+        })
+    }
+}
+
+impl Default for HazardEraReclaimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A guard entered on a [`HazardEraReclaimer`].
+pub struct HazardEraGuard<'a> {
+    reclaimer: &'a HazardEraReclaimer,
+    slot: &'a Slot<Reservation>,
+}
+
+impl Drop for HazardEraGuard<'_> {
+    fn drop(&mut self) {
+        let reservation = &self.slot.state;
+        reservation.lower.store(NONE, Ordering::Release);
+        reservation.upper.store(NONE, Ordering::Release);
+        self.reclaimer.slots.release(self.slot);
+    }
+}
+
+/// A node of a [`HazardEraReclaimer`], stamped with the era it was
+/// allocated in.
+#[derive(Debug)]
+pub struct Linked<T> {
+    birth: u64,
+    value: T,
+}
+
+impl<T> Deref for Linked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Linked<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+unsafe impl Reclaimer for HazardEraReclaimer {
+    type Linked<T> = Linked<T>;
+    type Guard<'a> = HazardEraGuard<'a>;
+
+    fn enter(&self) -> HazardEraGuard<'_> {
+        let slot = self.slots.acquire();
+        let era = self.era.load(Ordering::Relaxed);
+        slot.state.lower.store(era, Ordering::Relaxed);
+        slot.state.upper.store(era, Ordering::Relaxed);
+        atomic::fence(Ordering::SeqCst);
+
+        HazardEraGuard {
+            reclaimer: self,
+            slot,
+        }
+    }
+
+    #[inline]
+    fn reborrow<'a>(guard: &'a HazardEraGuard<'_>) -> &'a HazardEraGuard<'a> {
+        guard
+    }
+
+    #[inline]
+    fn owns(&self, guard: &HazardEraGuard<'_>) -> bool {
+        std::ptr::eq(guard.reclaimer, self)
+    }
+
+    #[inline]
+    fn link_boxed<T>(&self, value: T) -> *mut Linked<T> {
+        if self
+            .allocated
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(ERA_FREQUENCY)
+        {
+            self.era.fetch_add(1, Ordering::SeqCst);
+        }
+
+        let birth = self.era.load(Ordering::Acquire);
+        Box::into_raw(Box::new(Linked { birth, value }))
+    }
+
+    #[inline]
+    fn protect<T>(
+        guard: &HazardEraGuard<'_>,
+        ptr: &AtomicPtr<Linked<T>>,
+        _ordering: Ordering,
+    ) -> *mut Linked<T> {
+        let upper = &guard.slot.state.upper;
+        let mut reserved = upper.load(Ordering::Relaxed);
+
+        // extend the reservation until it covers the era the pointer was
+        // read in, so the node cannot have been born after it
+        loop {
+            let loaded = ptr.load(Ordering::SeqCst);
+            let era = guard.reclaimer.era.load(Ordering::SeqCst);
+            if era == reserved {
+                return loaded;
+            }
+            upper.store(era, Ordering::Relaxed);
+            atomic::fence(Ordering::SeqCst);
+            reserved = era;
+        }
+    }
+
+    unsafe fn retire<T>(&self, ptr: *mut Linked<T>) {
+        let birth = (*ptr).birth;
+        atomic::fence(Ordering::SeqCst);
+        let era = self.era.load(Ordering::Relaxed);
+
+        let mut retired = self.retired.lock().unwrap();
+        retired.push(Retired::new(ptr, era, birth));
+        if retired.len() < COLLECT_AFTER {
+            return;
+        }
+
+        atomic::fence(Ordering::SeqCst);
+        let (pending, ready) = mem::take(&mut *retired)
+            .into_iter()
+            .partition::<Vec<_>, _>(|node| self.is_reserved(node));
+        *retired = pending;
+        drop(retired);
+
+        for node in ready {
+            node.free();
+        }
+    }
+
+    #[inline]
+    unsafe fn free<T>(&self, ptr: *mut Linked<T>) {
+        drop(Box::from_raw(ptr));
+    }
+}
+
+impl Drop for HazardEraReclaimer {
+    fn drop(&mut self) {
+        for node in self.retired.get_mut().unwrap().drain(..) {
+            unsafe { node.free() };
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::alloc::{self, Layout};
+use std::cell::RefCell;
+use std::ptr::NonNull;
+
+// How many free allocations of each layout a thread holds on to.
+const CAPACITY: usize = 256;
+
+thread_local! {
+    static POOL: RefCell<Pool> = const { RefCell::new(Pool { lists: Vec::new() }) };
+}
+
+// Freed node allocations of the current thread, by layout. The containers
+// only ever allocate a couple of node types, so a linear scan beats hashing.
+struct Pool {
+    lists: Vec<(Layout, Vec<NonNull<u8>>)>,
+}
+
+impl Pool {
+    fn list(&mut self, layout: Layout) -> &mut Vec<NonNull<u8>> {
+        let i = match self.lists.iter().position(|(l, _)| *l == layout) {
+            Some(i) => i,
+            None => {
+                self.lists.push((layout, Vec::new()));
+                self.lists.len() - 1
+            }
+        };
+        &mut self.lists[i].1
+    }
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        for (layout, list) in self.lists.drain(..) {
+            for ptr in list {
+                unsafe { alloc::dealloc(ptr.as_ptr(), layout) };
+            }
+        }
+    }
+}
+
+/// Takes a free allocation for a `T` from the current thread's pool.
+#[inline]
+pub(super) fn take<T>() -> Option<NonNull<T>> {
+    POOL.try_with(|pool| pool.borrow_mut().list(Layout::new::<T>()).pop())
+        .ok()
+        .flatten()
+        .map(NonNull::cast)
+}
+
+/// Allocates into the current thread's pool until it holds `n` free
+/// allocations for a `T`, or as many as it keeps.
+pub(super) fn reserve<T>(n: usize) {
+    let layout = Layout::new::<T>();
+    if layout.size() == 0 {
+        return;
+    }
+
+    let _ = POOL.try_with(|pool| {
+        let mut pool = pool.borrow_mut();
+        let list = pool.list(layout);
+        while list.len() < n.min(CAPACITY) {
+            let ptr = unsafe { alloc::alloc(layout) };
+            list.push(NonNull::new(ptr).unwrap_or_else(|| alloc::handle_alloc_error(layout)));
+        }
+    });
+}
+
+/// Drops the `T` at `ptr` and hands its allocation to the current thread's
+/// pool, or back to the global allocator if the pool is full.
+///
+/// # Safety
+///
+/// `ptr` must come from a `Box<T>` or from [`take`], and be unreachable.
+pub(super) unsafe fn put<T>(ptr: *mut T) {
+    ptr.drop_in_place();
+
+    let layout = Layout::new::<T>();
+    if layout.size() == 0 {
+        return;
+    }
+
+    let ptr = NonNull::new_unchecked(ptr.cast::<u8>());
+    let kept = POOL
+        .try_with(|pool| {
+            let mut pool = pool.borrow_mut();
+            let list = pool.list(layout);
+            if list.len() == CAPACITY {
+                return false;
+            }
+            list.push(ptr);
+            true
+        })
+        .unwrap_or(false);
+
+    if !kept {
+        alloc::dealloc(ptr.as_ptr(), layout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuse() {
+        let a = Box::into_raw(Box::new(1_u64));
+        unsafe { put(a) };
+
+        let b = take::<u64>().unwrap();
+        assert_eq!(b.as_ptr(), a);
+        assert!(take::<u64>().is_none());
+        assert!(take::<u32>().is_none());
+
+        unsafe {
+            b.as_ptr().write(3);
+            put(b.as_ptr());
+        }
+        for _ in 0..CAPACITY + 1 {
+            unsafe { put(Box::into_raw(Box::new(2_u64))) };
+        }
+        POOL.with(|pool| assert_eq!(pool.borrow_mut().list(Layout::new::<u64>()).len(), CAPACITY));
+    }
+
+    #[test]
+    fn reserve_fills() {
+        let len = || POOL.with(|pool| pool.borrow_mut().list(Layout::new::<[u64; 3]>()).len());
+
+        reserve::<[u64; 3]>(2);
+        assert_eq!(len(), 2);
+        let a = take::<[u64; 3]>().unwrap();
+        reserve::<[u64; 3]>(2);
+        assert_eq!(len(), 2);
+        unsafe { put(a.as_ptr()) };
+        assert_eq!(len(), 3);
+
+        reserve::<[u64; 3]>(CAPACITY + 1);
+        assert_eq!(len(), CAPACITY);
+    }
+}
@@ -0,0 +1,191 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::mem::MaybeUninit;
+
+use crate::sync::{spin_loop, thread_local, AtomicUsize, Ordering};
+
+// Set in `Slot::readers` once a pop took the value.
+const TAKEN: usize = 1 << (usize::BITS - 1);
+
+thread_local! {
+    // The slots the current thread is reading, so that a pop from inside a
+    // reader panics instead of waiting for itself. loom's version of the
+    // macro has no `const` form.
+    #[allow(clippy::missing_const_for_thread_local)]
+    static READING: RefCell<Vec<usize>> = RefCell::new(Vec::new());
+}
+
+/// The value of a node, which a pop moves out while other threads may still
+/// be looking at it. A guard only keeps the node allocated, not the value,
+/// so readers register for as long as they borrow it and the pop waits for
+/// them before handing the value to its caller, who may drop it right away.
+pub(crate) struct Slot<T> {
+    // the readers borrowing the value, plus `TAKEN` once a pop took it
+    readers: AtomicUsize,
+    value: MaybeUninit<T>,
+}
+
+impl<T> Slot<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Self {
+            readers: AtomicUsize::new(0),
+            value: MaybeUninit::new(value),
+        }
+    }
+
+    /// A slot that never had a value, as in the queue's sentinel.
+    pub(crate) fn empty() -> Self {
+        Self {
+            readers: AtomicUsize::new(TAKEN),
+            value: MaybeUninit::uninit(),
+        }
+    }
+
+    /// Calls `f` with the value, or returns `None` if a pop already took it.
+    ///
+    /// A pop of this value on another thread waits for `f` to return, one on
+    /// this thread from inside `f` panics.
+    #[inline]
+    pub(crate) fn read<U>(&self, f: impl FnOnce(&T) -> U) -> Option<U> {
+        // ordered against the pop's `fetch_or` like any two RMWs, so either
+        // this sees `TAKEN` or the pop sees this reader
+        if self.readers.fetch_add(1, Ordering::Relaxed) & TAKEN != 0 {
+            self.readers.fetch_sub(1, Ordering::Relaxed);
+            return None;
+        }
+
+        let _reader = Reader::register(self);
+        Some(f(unsafe { self.value.assume_init_ref() }))
+    }
+
+    /// Moves the value out, once no reader borrows it anymore.
+    ///
+    /// # Safety
+    ///
+    /// The caller must own the value, by having won the CAS that removed its
+    /// node, and take it only once.
+    #[inline]
+    pub(crate) unsafe fn take(&self) -> T {
+        let mut readers = self.readers.fetch_or(TAKEN, Ordering::Acquire);
+        if readers != 0 && self.is_read_here() {
+            panic!("popped an element the same thread is still reading");
+        }
+
+        while readers & !TAKEN != 0 {
+            spin_loop();
+            // an RMW reads the latest count, where a load could keep
+            // returning one a reader already left
+            readers = self.readers.fetch_or(TAKEN, Ordering::Acquire);
+        }
+        self.value.assume_init_read()
+    }
+
+    fn is_read_here(&self) -> bool {
+        let addr = self as *const Self as usize;
+        READING
+            .try_with(|reading| reading.borrow().contains(&addr))
+            .unwrap_or(false)
+    }
+
+    /// Returns the value without registering as a reader.
+    ///
+    /// # Safety
+    ///
+    /// The value must not be taken while the reference is alive, which holds
+    /// when the container is borrowed mutably.
+    #[cfg(any(test, debug_assertions))]
+    #[inline]
+    pub(crate) unsafe fn get(&self) -> &T {
+        self.value.assume_init_ref()
+    }
+
+    /// Drops the value in place.
+    ///
+    /// # Safety
+    ///
+    /// The value must not have been taken, and nothing else may access it.
+    #[inline]
+    pub(crate) unsafe fn drop_value(&mut self) {
+        self.value.assume_init_drop();
+    }
+}
+
+impl<T> fmt::Debug for Slot<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Slot").finish_non_exhaustive()
+    }
+}
+
+// Unregisters a reader, also when `f` panics.
+struct Reader<'a> {
+    readers: &'a AtomicUsize,
+    // whether the slot was pushed to `READING`, which fails while the
+    // thread's locals are being torn down
+    listed: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn register<T>(slot: &'a Slot<T>) -> Self {
+        let addr = slot as *const Slot<T> as usize;
+        let listed = READING
+            .try_with(|reading| reading.borrow_mut().push(addr))
+            .is_ok();
+        Self {
+            readers: &slot.readers,
+            listed,
+        }
+    }
+}
+
+impl Drop for Reader<'_> {
+    fn drop(&mut self) {
+        if self.listed {
+            let _ = READING.try_with(|reading| reading.borrow_mut().pop());
+        }
+        self.readers.fetch_sub(1, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Barrier;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn read_take() {
+        let slot = Slot::new(String::from("a"));
+        assert_eq!(slot.read(String::len), Some(1));
+        assert_eq!(unsafe { slot.take() }, "a");
+        assert_eq!(slot.read(String::len), None);
+        assert_eq!(Slot::<String>::empty().read(String::len), None);
+    }
+
+    #[test]
+    fn take_waits_for_readers() {
+        let slot = Slot::new(vec![1, 2, 3]);
+        let b = Barrier::new(2);
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                slot.read(|v| {
+                    b.wait();
+                    thread::yield_now();
+                    assert_eq!(v, &[1, 2, 3]);
+                })
+                .unwrap();
+            });
+
+            b.wait();
+            drop(unsafe { slot.take() });
+        });
+    }
+
+    #[test]
+    #[should_panic = "popped an element the same thread is still reading"]
+    fn take_while_reading() {
+        let slot = Slot::new(1);
+        slot.read(|_| unsafe { slot.take() });
+    }
+}
@@ -0,0 +1,21 @@
+//! The atomics the containers are built on, which are loom's when the crate
+//! is model checked:
+//!
+//! ```text
+//! RUSTFLAGS="--cfg loom" cargo test --release --lib models
+//! ```
+
+#[cfg(loom)]
+pub(crate) use loom::{
+    hint::spin_loop,
+    sync::atomic::{AtomicPtr, AtomicU8, AtomicUsize},
+    thread_local,
+};
+#[cfg(not(loom))]
+pub(crate) use std::{
+    hint::spin_loop,
+    sync::atomic::{AtomicPtr, AtomicU8, AtomicUsize},
+    thread_local,
+};
+
+pub(crate) use std::sync::atomic::Ordering;
@@ -0,0 +1,249 @@
+//! Helpers shared by the tests and models of the containers.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Barrier;
+use std::thread;
+
+use crate::reclaim::Reclaimer;
+use crate::{doubly, queue::Queue, LinkedList};
+
+const THREADS: usize = 4;
+const ITER: usize = if cfg!(miri) { 20 } else { 1000 };
+
+/// The operations the shared tests drive on every container.
+pub(crate) trait Container<T>: Sync {
+    fn push_back(&self, value: T);
+
+    fn pop_front(&self) -> Option<T>;
+
+    fn pop_front_if(&self, predicate: impl FnMut(&T) -> bool) -> Option<T>;
+
+    fn approximate_len(&self) -> usize;
+
+    fn is_empty(&self) -> bool;
+}
+
+impl<T: Send + Sync, R: Reclaimer> Container<T> for LinkedList<T, R> {
+    fn push_back(&self, value: T) {
+        LinkedList::push_back(self, value);
+    }
+
+    fn pop_front(&self) -> Option<T> {
+        LinkedList::pop_front(self)
+    }
+
+    fn pop_front_if(&self, predicate: impl FnMut(&T) -> bool) -> Option<T> {
+        LinkedList::pop_front_if(self, predicate)
+    }
+
+    fn approximate_len(&self) -> usize {
+        LinkedList::approximate_len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        LinkedList::is_empty(self)
+    }
+}
+
+impl<T: Send + Sync, R: Reclaimer> Container<T> for doubly::LinkedList<T, R> {
+    fn push_back(&self, value: T) {
+        doubly::LinkedList::push_back(self, value);
+    }
+
+    fn pop_front(&self) -> Option<T> {
+        doubly::LinkedList::pop_front(self)
+    }
+
+    fn pop_front_if(&self, predicate: impl FnMut(&T) -> bool) -> Option<T> {
+        doubly::LinkedList::pop_front_if(self, predicate)
+    }
+
+    fn approximate_len(&self) -> usize {
+        doubly::LinkedList::approximate_len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        doubly::LinkedList::is_empty(self)
+    }
+}
+
+impl<T: Send + Sync, R: Reclaimer> Container<T> for Queue<T, R> {
+    fn push_back(&self, value: T) {
+        Queue::push_back(self, value);
+    }
+
+    fn pop_front(&self) -> Option<T> {
+        Queue::pop_front(self)
+    }
+
+    fn pop_front_if(&self, predicate: impl FnMut(&T) -> bool) -> Option<T> {
+        Queue::pop_front_if(self, predicate)
+    }
+
+    fn approximate_len(&self) -> usize {
+        Queue::approximate_len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        Queue::is_empty(self)
+    }
+}
+
+/// Checks that `approximate_len` stays within its bound while every thread
+/// holds at most one element or operation at a time.
+pub(crate) fn len_bounds(container: &impl Container<usize>) {
+    let b = Barrier::new(THREADS + 1);
+    thread::scope(|s| {
+        let handles: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let b = &b;
+                s.spawn(move || {
+                    b.wait();
+                    for i in 0..ITER {
+                        container.push_back(i);
+                        assert!(container.pop_front().is_some());
+                    }
+                })
+            })
+            .collect();
+
+        b.wait();
+        while !handles.iter().all(|h| h.is_finished()) {
+            assert!(container.approximate_len() <= THREADS);
+        }
+    });
+    assert!(container.is_empty());
+}
+
+/// Counts its drops in the counter it borrows.
+pub(crate) struct DropCount<'a>(pub(crate) &'a AtomicUsize);
+
+impl Drop for DropCount<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+// xorshift, so the model tests are reproducible without extra dependencies
+pub(crate) struct Rng(pub(crate) u64);
+
+impl Rng {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/// Races filtered pops of the even elements against plain pops. The
+/// elements are boxed, so that a predicate looking at an element another pop
+/// already dropped reads freed memory.
+pub(crate) fn pop_front_if_multi(container: &impl Container<Box<usize>>) {
+    for i in 0..ITER {
+        container.push_back(Box::new(i));
+    }
+
+    let b = Barrier::new(THREADS);
+    let taken = thread::scope(|s| {
+        let handles: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let b = &b;
+                s.spawn(move || {
+                    b.wait();
+                    let mut taken = Vec::new();
+                    while !container.is_empty() {
+                        let popped = if t % 2 == 0 {
+                            container.pop_front_if(|x| **x % 2 == 0)
+                        } else {
+                            container.pop_front()
+                        };
+                        if let Some(x) = popped {
+                            taken.push((t, x));
+                        }
+                    }
+                    taken
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    assert!(taken.iter().all(|(t, x)| t % 2 == 1 || **x % 2 == 0));
+    let mut values: Vec<_> = taken.into_iter().map(|(_, x)| *x).collect();
+    values.sort();
+    assert!(values.into_iter().eq(0..ITER));
+}
+
+/// Pops the element a predicate is looking at from inside the predicate,
+/// which panics.
+pub(crate) fn pop_front_if_reentrant(container: &impl Container<usize>) {
+    container.push_back(1);
+    container.push_back(2);
+    container.pop_front_if(|_| container.pop_front().is_some());
+}
+
+#[cfg(loom)]
+pub(crate) mod models {
+    use std::sync::Arc;
+
+    use loom::thread;
+
+    use super::Container;
+    use crate::reclaim::CheckedReclaimer;
+    use crate::{doubly, queue::Queue, LinkedList};
+
+    /// A container on a [`CheckedReclaimer`] that the models race operations
+    /// on.
+    pub(crate) trait Checked: Container<u32> + Extend<u32> + Send + Sized + 'static {
+        fn new() -> Self;
+
+        fn with_values(values: &[u32]) -> Arc<Self> {
+            let mut container = Self::new();
+            container.extend(values.iter().copied());
+            Arc::new(container)
+        }
+
+        fn drain(&self) -> Vec<u32> {
+            std::iter::from_fn(|| self.pop_front()).collect()
+        }
+    }
+
+    impl Checked for LinkedList<u32, CheckedReclaimer> {
+        fn new() -> Self {
+            Self::with_reclaimer(CheckedReclaimer::new())
+        }
+    }
+
+    impl Checked for doubly::LinkedList<u32, CheckedReclaimer> {
+        fn new() -> Self {
+            Self::with_reclaimer(CheckedReclaimer::new())
+        }
+    }
+
+    impl Checked for Queue<u32, CheckedReclaimer> {
+        fn new() -> Self {
+            Self::with_reclaimer(CheckedReclaimer::new())
+        }
+    }
+
+    // runs `f` on another thread while `g` runs on this one
+    pub(crate) fn race<C: Checked, A, B>(
+        container: &Arc<C>,
+        f: impl FnOnce(&C) -> A + Send + 'static,
+        g: impl FnOnce(&C) -> B,
+    ) -> (A, B)
+    where
+        A: Send + 'static,
+    {
+        let other = thread::spawn({
+            let container = container.clone();
+            move || f(&container)
+        });
+        let b = g(container);
+        (other.join().unwrap(), b)
+    }
+}